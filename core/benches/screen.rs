@@ -0,0 +1,83 @@
+use chippers_core::{core::Palette, interpreter::Interpreter};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const STEPS: usize = 1000;
+
+#[rustfmt::skip]
+const DRAW_LOOP: [u8; 8] = [
+    0x60, 0x05, // LD V0, 5
+    0xA0, 0x00, // LD I, 0x000
+    0xD0, 0x05, // DRW V0, V0, 5
+    0x12, 0x04, // JP 0x204
+];
+
+#[rustfmt::skip]
+const CLEAR_LOOP: [u8; 4] = [
+    0x00, 0xE0, // CLS
+    0x12, 0x00, // JP 0x200
+];
+
+#[rustfmt::skip]
+const SCROLL_LOOP: [u8; 16] = [
+    0x00, 0xFF, // HIGH
+    0xF3, 0x01, // PLANE 3
+    0xA0, 0x00, // LD I, 0x000
+    0xD0, 0x00, // DRW V0, V0, 0
+    0x00, 0xC1, // SCD 1
+    0x00, 0xFB, // SCR
+    0x00, 0xFC, // SCL
+    0x12, 0x06, // JP 0x206
+];
+
+fn run(c: &mut Criterion, name: &str, program: &[u8]) {
+    let mut interpreter = Interpreter::new(program).unwrap();
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            for _ in 0..STEPS {
+                interpreter.step().unwrap();
+            }
+        })
+    });
+}
+
+fn draw(c: &mut Criterion) {
+    run(c, "draw", &DRAW_LOOP);
+}
+
+fn clear(c: &mut Criterion) {
+    run(c, "clear", &CLEAR_LOOP);
+}
+
+fn scroll(c: &mut Criterion) {
+    run(c, "scroll", &SCROLL_LOOP);
+}
+
+fn rgba(c: &mut Criterion) {
+    let mut interpreter = Interpreter::new(&SCROLL_LOOP).unwrap();
+    for _ in 0..4 {
+        interpreter.step().unwrap();
+    }
+    let palette = Palette::default();
+    let mut buffer = vec![0; 128 * 64 * 4];
+
+    c.bench_function("rgba", |b| {
+        b.iter(|| {
+            black_box(&interpreter.screen).write_rgba(black_box(&palette), &mut buffer);
+        })
+    });
+}
+
+fn diff(c: &mut Criterion) {
+    let empty = Interpreter::new(&CLEAR_LOOP).unwrap();
+    let mut drawn = Interpreter::new(&DRAW_LOOP).unwrap();
+    for _ in 0..3 {
+        drawn.step().unwrap();
+    }
+
+    c.bench_function("diff", |b| {
+        b.iter(|| black_box(&drawn.screen).changed_rows(black_box(&empty.screen)))
+    });
+}
+
+criterion_group!(benches, draw, clear, scroll, rgba, diff);
+criterion_main!(benches);
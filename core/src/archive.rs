@@ -0,0 +1,56 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+pub struct RomFile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Reads the ROMs at `path`.
+///
+/// `.zip` archives yield every `.ch8` file they contain and `.gz` files are
+/// decompressed, anything else is read as a single ROM.
+pub fn read_roms(path: &Path) -> io::Result<Vec<RomFile>> {
+    let name = path.display().to_string();
+
+    // Extensions are matched case-insensitively, ROM packs often use GAME.ZIP
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    match extension {
+        Some(extension) if extension.eq_ignore_ascii_case("zip") => {
+            let mut archive = ZipArchive::new(File::open(path)?)?;
+            let mut roms = Vec::new();
+
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index)?;
+                if !entry.is_file() || !entry.name().to_ascii_lowercase().ends_with(".ch8") {
+                    continue;
+                }
+
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data)?;
+                roms.push(RomFile {
+                    name: format!("{name}/{}", entry.name()),
+                    data,
+                });
+            }
+
+            Ok(roms)
+        }
+        Some(extension) if extension.eq_ignore_ascii_case("gz") => {
+            let mut data = Vec::new();
+            GzDecoder::new(File::open(path)?).read_to_end(&mut data)?;
+            let name = path.with_extension("").display().to_string();
+            Ok(vec![RomFile { name, data }])
+        }
+        _ => Ok(vec![RomFile {
+            name,
+            data: std::fs::read(path)?,
+        }]),
+    }
+}
@@ -0,0 +1,112 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::interpreter::{Interpreter, StepOutcome};
+
+/// Renders the interpreter's sound output as `f32` samples, ready for an audio
+/// backend. Without XO-CHIP audio instructions this is the classic 500 Hz beep.
+#[derive(Clone, Copy, Debug)]
+pub struct AudioGenerator {
+    pub sample_rate: u32,
+    // Peak amplitude of the square wave, between 0.0 and 1.0
+    pub volume: f32,
+    // Position in the pattern, in bits, carried over between buffers
+    phase: f32,
+}
+
+impl AudioGenerator {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            volume: 0.25,
+            phase: 0.0,
+        }
+    }
+
+    /// Fills `buffer` with mono samples for the interpreter's current sound timer,
+    /// audio pattern and pitch. Consecutive calls continue the waveform.
+    pub fn fill(&mut self, interpreter: &Interpreter, buffer: &mut [f32]) {
+        match interpreter.sound_timer.value {
+            0 => self.silence(buffer),
+            _ => self.tone(interpreter, buffer),
+        }
+    }
+
+    fn silence(&mut self, buffer: &mut [f32]) {
+        buffer.fill(0.0);
+        self.phase = 0.0;
+    }
+
+    fn tone(&mut self, interpreter: &Interpreter, buffer: &mut [f32]) {
+        let step = interpreter.playback_rate() / self.sample_rate as f32;
+        for sample in buffer {
+            *sample = match pattern_bit(interpreter.audio_pattern(), self.phase) {
+                true => self.volume,
+                false => -self.volume,
+            };
+            self.phase = (self.phase + step) % 128.0;
+        }
+    }
+}
+
+// Bit of the 128-bit pattern at `phase`, most significant bit first
+pub(crate) fn pattern_bit(pattern: &[u8; 16], phase: f32) -> bool {
+    let bit = phase as usize % 128;
+    pattern[bit / 8] >> (7 - bit % 8) & 1 != 0
+}
+
+/// Receives the sound output of each frame run with [`Interpreter::run_frame`].
+pub trait AudioSink {
+    fn beep_on(&mut self);
+    fn beep_off(&mut self);
+    // One frame's worth of samples, silent ones included
+    fn queue_samples(&mut self, samples: &[f32]);
+}
+
+/// Discards all sound, for headless use.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullAudioSink;
+
+impl AudioSink for NullAudioSink {
+    fn beep_on(&mut self) {}
+
+    fn beep_off(&mut self) {}
+
+    fn queue_samples(&mut self, _samples: &[f32]) {}
+}
+
+// An installed sink with the generator feeding it
+pub(crate) struct AudioOutput {
+    sink: Box<dyn AudioSink + Send>,
+    generator: AudioGenerator,
+    // Reused between frames to avoid allocating
+    samples: Vec<f32>,
+}
+
+impl AudioOutput {
+    pub(crate) fn new(sink: Box<dyn AudioSink + Send>, sample_rate: u32) -> Self {
+        Self {
+            sink,
+            generator: AudioGenerator::new(sample_rate),
+            samples: Vec::new(),
+        }
+    }
+
+    pub(crate) fn play_frame(&mut self, interpreter: &Interpreter, frame: &StepOutcome) {
+        if frame.sound_started {
+            self.sink.beep_on();
+        }
+
+        let length = (self.generator.sample_rate / interpreter.clock.timer_rate()) as usize;
+        self.samples.resize(length, 0.0);
+        // A frame whose tick stopped the sound was still audible
+        match interpreter.sound_timer.value > 0 || frame.sound_stopped {
+            true => self.generator.tone(interpreter, &mut self.samples),
+            false => self.generator.silence(&mut self.samples),
+        }
+        self.sink.queue_samples(&self.samples);
+
+        if frame.sound_stopped {
+            self.sink.beep_off();
+        }
+    }
+}
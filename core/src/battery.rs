@@ -0,0 +1,96 @@
+#[cfg(feature = "std")]
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use core::str::FromStr;
+
+#[cfg(feature = "std")]
+use crate::hash::crc32;
+use crate::{
+    core::{Ram, Segment},
+    error::BatteryError,
+    interpreter::Interpreter,
+};
+
+/// A memory range kept across sessions, like a cartridge's battery-backed RAM,
+/// for games that keep their high scores or progress in memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatteryRange {
+    pub address: u16,
+    pub length: u16,
+}
+
+impl BatteryRange {
+    /// The range's contents, or `None` if it doesn't fit in memory.
+    pub fn read<'a>(&self, ram: &'a Ram) -> Option<&'a [u8]> {
+        let start = self.address as usize;
+        ram.get(start..start + self.length as usize)
+    }
+
+    /// Writes saved contents back into memory, once the program is loaded.
+    pub fn restore(&self, interpreter: &mut Interpreter, data: &[u8]) -> Result<(), BatteryError> {
+        if data.len() != self.length as usize {
+            return Err(BatteryError::SizeMismatch {
+                size: data.len(),
+                expected: self.length as usize,
+            });
+        }
+        interpreter.load_segments(&[Segment {
+            address: self.address,
+            data,
+        }])?;
+        Ok(())
+    }
+}
+
+/// Parses `<address>:<length>`, the address in hexadecimal, e.g. `0x2F0:16`.
+impl FromStr for BatteryRange {
+    type Err = BatteryError;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let (address, length) = range.split_once(':').ok_or(BatteryError::InvalidFormat)?;
+
+        let address = address.strip_prefix("0x").unwrap_or(address);
+        let address = u16::from_str_radix(address, 16).map_err(|_| BatteryError::InvalidFormat)?;
+        let length = match length.parse() {
+            Ok(length @ 1..) => length,
+            _ => return Err(BatteryError::InvalidFormat),
+        };
+
+        Ok(Self { address, length })
+    }
+}
+
+#[cfg(feature = "std")]
+impl BatteryRange {
+    /// Where a program's save lives in `directory`. Like high-score tables, saves are
+    /// named after the program's CRC-32.
+    pub fn path(directory: &Path, program: &[u8]) -> PathBuf {
+        directory.join(format!("{:08x}.sav", crc32(program)))
+    }
+
+    /// Restores the save written by [`BatteryRange::save`], returning false if there
+    /// is none yet.
+    pub fn load(&self, path: &Path, interpreter: &mut Interpreter) -> io::Result<bool> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error),
+        };
+        self.restore(interpreter, &data)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(true)
+    }
+
+    pub fn save(&self, path: &Path, ram: &Ram) -> io::Result<()> {
+        let data = self.read(ram).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "battery range does not fit in memory",
+            )
+        })?;
+        fs::write(path, data)
+    }
+}
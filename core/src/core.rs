@@ -1,26 +1,110 @@
-use std::{
-    fmt::Display,
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{
+    fmt::{Debug, Display},
     ops::{Index, IndexMut, Range},
 };
 
-pub struct Ram([u8; 4096]);
+use crate::{error::LoadError, hash::StableHasher};
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ram(Box<[u8]>);
 
 impl Ram {
-    pub(crate) fn new() -> Self {
-        let mut buffer = [0; 4096];
+    // 64 KiB is the most 16 bit addresses can reach
+    pub(crate) const MAX_SIZE: usize = 0x10000;
+
+    pub(crate) fn new(
+        size: usize,
+        font: &[u8; 80],
+        big_font: &[u8; 160],
+    ) -> Result<Self, LoadError> {
+        Self::check_size(size)?;
+        let mut ram = Self(vec![0; size].into_boxed_slice());
+        ram.write_fonts(font, big_font);
+        Ok(ram)
+    }
+
+    // Clears memory in place, leaving only the fonts
+    pub(crate) fn reset(&mut self, font: &[u8; 80], big_font: &[u8; 160]) {
+        self.0.fill(0);
+        self.write_fonts(font, big_font);
+    }
+
+    fn write_fonts(&mut self, font: &[u8; 80], big_font: &[u8; 160]) {
+        self.0[..font.len()].copy_from_slice(font);
+        let big_font_range = BIG_FONT_ADDRESS as usize..BIG_FONT_ADDRESS as usize + big_font.len();
+        self.0[big_font_range].copy_from_slice(big_font);
+    }
+
+    pub(crate) fn from_image(image: &[u8]) -> Result<Self, LoadError> {
+        Self::check_size(image.len())?;
+        Ok(Self(image.into()))
+    }
 
-        // Initialize font data in RAM
-        for (font_data, memory_cell) in FONT_DATA.into_iter().zip(buffer.iter_mut()) {
-            *memory_cell = font_data
+    // There must be room for a program past the interpreter area
+    fn check_size(size: usize) -> Result<(), LoadError> {
+        if size <= 0x200 || size > Self::MAX_SIZE {
+            return Err(LoadError::InvalidMemorySize { size });
         }
+        Ok(())
+    }
 
-        Self(buffer)
+    pub fn len(&self) -> usize {
+        self.0.len()
     }
 
-    pub(crate) fn load_program(&mut self, program: &[u8]) {
-        for (offset, byte) in program.iter().copied().enumerate() {
-            self.0[0x200 + offset] = byte;
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn load_program(&mut self, program: &[u8], address: u16) -> Result<(), LoadError> {
+        self.check_program(program, address)?;
+        self.load_segment(address, program)
+    }
+
+    pub(crate) fn check_program(&self, program: &[u8], address: u16) -> Result<(), LoadError> {
+        // Odd sizes are fine, programs often end with a lone byte of sprite data
+        if program.is_empty() {
+            return Err(LoadError::Empty);
         }
+
+        let max = self.0.len().saturating_sub(address as usize);
+        if program.len() > max {
+            return Err(LoadError::TooLarge {
+                size: program.len(),
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn load_segment(&mut self, address: u16, data: &[u8]) -> Result<(), LoadError> {
+        let start = address as usize;
+        let destination =
+            self.0
+                .get_mut(start..start + data.len())
+                .ok_or(LoadError::SegmentOutOfBounds {
+                    address,
+                    size: data.len(),
+                })?;
+        destination.copy_from_slice(data);
+
+        Ok(())
+    }
+
+    /// Reads a byte, `None` past the end of memory.
+    pub fn read(&self, address: u16) -> Option<u8> {
+        self.0.get(address as usize).copied()
+    }
+
+    pub(crate) fn write(&mut self, address: u16, value: u8) -> Option<()> {
+        *self.0.get_mut(address as usize)? = value;
+        Some(())
+    }
+
+    pub(crate) fn get(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.0.get(range)
     }
 }
 
@@ -46,6 +130,116 @@ impl IndexMut<u16> for Ram {
     }
 }
 
+// Only the first bytes are shown, the rest is mostly zeroes
+impl Debug for Ram {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let shown = self.len().min(64);
+        f.debug_struct("Ram")
+            .field("len", &self.len())
+            .field("data", &format_args!("{:02X?}..", &self.0[..shown]))
+            .finish()
+    }
+}
+
+/// A block of data to be loaded at a specific address.
+pub struct Segment<'a> {
+    pub address: u16,
+    pub data: &'a [u8],
+}
+
+/// Equally sized memory banks sharing a single RAM window.
+///
+/// Switching banks saves the window contents back into the previously selected
+/// bank, so writes made by the program persist across switches.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Banks {
+    window: u16,
+    selected: usize,
+    banks: Vec<Vec<u8>>,
+}
+
+impl Banks {
+    pub fn new(window: u16, banks: Vec<Vec<u8>>) -> Result<Self, LoadError> {
+        // Whether the window fits in memory is checked when the banks are installed or restored
+        let bank_size = banks.first().map_or(0, Vec::len);
+        if let Some(bank) = banks.iter().position(|bank| bank.len() != bank_size) {
+            return Err(LoadError::BankSizeMismatch { bank });
+        }
+
+        Ok(Self {
+            window,
+            selected: 0,
+            banks,
+        })
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    // Restores the selection without touching RAM, whose window already holds the bank
+    pub(crate) fn with_selected(mut self, bank: usize) -> Result<Self, LoadError> {
+        if bank >= self.banks.len() {
+            return Err(LoadError::InvalidBank { bank });
+        }
+        self.selected = bank;
+        Ok(self)
+    }
+
+    pub(crate) fn window(&self) -> u16 {
+        self.window
+    }
+
+    // Checks that the window fits in memory, for banks restored without being installed
+    pub(crate) fn check_window(&self, ram: &Ram) -> Result<(), LoadError> {
+        let size = self.banks.first().map_or(0, Vec::len);
+        if self.window as usize + size > ram.len() {
+            return Err(LoadError::SegmentOutOfBounds {
+                address: self.window,
+                size,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn contents(&self) -> &[Vec<u8>] {
+        &self.banks
+    }
+
+    pub fn len(&self) -> usize {
+        self.banks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.banks.is_empty()
+    }
+
+    pub(crate) fn load(&self, ram: &mut Ram) -> Result<(), LoadError> {
+        match self.banks.get(self.selected) {
+            Some(bank) => ram.load_segment(self.window, bank),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn switch(&mut self, ram: &mut Ram, bank: usize) -> Result<(), LoadError> {
+        if bank >= self.banks.len() {
+            return Err(LoadError::InvalidBank { bank });
+        }
+
+        // Save the window into the current bank before swapping in the new one
+        let start = self.window as usize;
+        let current = &mut self.banks[self.selected];
+        let end = start + current.len();
+        current.copy_from_slice(&ram[start..end]);
+
+        self.selected = bank;
+        self.load(ram)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VariableRegisters([u8; 16]);
 
 impl VariableRegisters {
@@ -80,6 +274,8 @@ impl IndexMut<usize> for VariableRegisters {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stack(Vec<u16>);
 
 impl Stack {
@@ -87,51 +283,423 @@ impl Stack {
         Self(Vec::new())
     }
 
+    pub(crate) fn from_addresses(addresses: Vec<u16>) -> Self {
+        Self(addresses)
+    }
+
     pub(crate) fn push(&mut self, address: u16) {
         self.0.push(address)
     }
 
-    pub(crate) fn pop(&mut self) -> u16 {
-        self.0.pop().unwrap()
+    pub(crate) fn pop(&mut self) -> Option<u16> {
+        self.0.pop()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Iterates over the return addresses, from the bottom of the stack to the top.
+    pub fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Display for Stack {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[")?;
+        for (depth, address) in self.iter().enumerate() {
+            if depth > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{address:#05X}")?;
+        }
+        write!(f, "]")
     }
 }
 
-pub struct Screen([bool; 32 * 64]);
+// Each row is packed into a u128, the most significant bit being the leftmost pixel.
+// In low resolution, only the top 32 rows and the upper 64 bits of each row are used,
+// and the two-page mode uses all 64 rows but only their upper 64 bits.
+// XO-CHIP adds a second bit plane, together they give each pixel one of four colors.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Screen {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_arrays::nested"))]
+    planes: [[u128; 64]; 2],
+    // Bit mask of the planes drawn to, cleared and scrolled
+    selected_planes: u8,
+    resolution: Resolution,
+}
+
+// Rows are summarized by their hash, `Display` draws them
+impl Debug for Screen {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Screen")
+            .field("resolution", &self.resolution)
+            .field("selected_planes", &self.selected_planes)
+            .field("hash", &format_args!("{:#018X}", self.hash()))
+            .finish_non_exhaustive()
+    }
+}
 
 impl Display for Screen {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{}", "-".repeat(129))?;
-        for row in 0..32 {
-            write!(f, "|")?;
-            for pixel in 0..64 {
-                let pixel_value = self.0[row * 64 + pixel];
-                let pixel_display = if pixel_value { "██" } else { "  " };
-                write!(f, "{pixel_display}")?;
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.display(ScreenFormat::default()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Resolution {
+    /// 64x32, the original CHIP-8 screen
+    Low,
+    /// 128x64, added by SUPER-CHIP
+    High,
+    /// 64x64, the two-page hi-res CHIP-8 variant used by ROMs starting with 1260
+    TwoPage,
+}
+
+#[derive(Clone, Copy)]
+pub enum Glyphs {
+    /// `█` for lit pixels, blank otherwise
+    Block,
+    /// `#` for lit pixels, `.` otherwise
+    HashDot,
+    /// `@` for lit pixels, blank otherwise
+    Ascii,
+    /// Two pixel rows per line of text using `▀`, `▄` and `█`
+    HalfBlock,
+}
+
+#[derive(Clone, Copy)]
+pub struct ScreenFormat {
+    pub glyphs: Glyphs,
+    /// Print each pixel twice horizontally, which keeps pixels roughly square
+    pub double_width: bool,
+    pub border: bool,
+}
+
+impl Default for ScreenFormat {
+    fn default() -> Self {
+        Self {
+            glyphs: Glyphs::Block,
+            double_width: true,
+            border: true,
+        }
+    }
+}
+
+/// RGBA colors, indexed by pixel color index.
+#[derive(Clone, Copy)]
+pub struct Palette(pub [[u8; 4]; 4]);
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self([
+            [0x00, 0x00, 0x00, 0xFF],
+            [0xFF, 0xFF, 0xFF, 0xFF],
+            [0xAA, 0xAA, 0xAA, 0xFF],
+            [0x55, 0x55, 0x55, 0xFF],
+        ])
+    }
+}
+
+pub struct ScreenDisplay<'a> {
+    screen: &'a Screen,
+    format: ScreenFormat,
+}
+
+impl Display for ScreenDisplay<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let cell_width = if self.format.double_width { 2 } else { 1 };
+        let rows_per_line = match self.format.glyphs {
+            Glyphs::HalfBlock => 2,
+            _ => 1,
+        };
+
+        let (width, height) = (self.screen.width(), self.screen.height());
+
+        if self.format.border {
+            writeln!(f, "{}", "-".repeat(width * cell_width + 2))?;
+        }
+        for row in (0..height).step_by(rows_per_line) {
+            if self.format.border {
+                write!(f, "|")?;
             }
-            writeln!(f, "|")?;
+            for pixel in 0..width {
+                let pixel_value = self.screen.get(pixel, row);
+                let pixel_display = match self.format.glyphs {
+                    Glyphs::Block if pixel_value => '█',
+                    Glyphs::HashDot if pixel_value => '#',
+                    Glyphs::HashDot => '.',
+                    Glyphs::Ascii if pixel_value => '@',
+                    Glyphs::HalfBlock => match (pixel_value, self.screen.get(pixel, row + 1)) {
+                        (false, false) => ' ',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (true, true) => '█',
+                    },
+                    _ => ' ',
+                };
+                for _ in 0..cell_width {
+                    write!(f, "{pixel_display}")?;
+                }
+            }
+            if self.format.border {
+                write!(f, "|")?;
+            }
+            writeln!(f)?;
+        }
+        if self.format.border {
+            writeln!(f, "{}", "-".repeat(width * cell_width + 2))?;
         }
-        writeln!(f, "{}", "-".repeat(129))?;
         Ok(())
     }
 }
 
 impl Screen {
     pub(crate) fn new() -> Self {
-        Self([false; 32 * 64])
+        Self {
+            planes: [[0; 64]; 2],
+            selected_planes: 0b01,
+            resolution: Resolution::Low,
+        }
     }
 
+    pub(crate) fn from_planes(
+        planes: [[u128; 64]; 2],
+        selected_planes: u8,
+        resolution: Resolution,
+    ) -> Self {
+        Self {
+            planes,
+            selected_planes,
+            resolution,
+        }
+    }
+
+    // Only the selected planes are cleared
     pub(crate) fn clear(&mut self) {
-        self.0 = [false; 32 * 64];
+        for plane in self.selected_planes_mut() {
+            *plane = [0; 64];
+        }
+    }
+
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    pub(crate) fn select_planes(&mut self, mask: u8) {
+        self.selected_planes = mask & 0b11;
+    }
+
+    fn selected_planes_mut(&mut self) -> impl Iterator<Item = &mut [u128; 64]> {
+        let mask = self.selected_planes;
+        self.planes
+            .iter_mut()
+            .enumerate()
+            .filter(move |(plane, _)| mask & 1 << plane != 0)
+            .map(|(_, rows)| rows)
+    }
+
+    pub fn width(&self) -> usize {
+        match self.resolution {
+            Resolution::High => 128,
+            Resolution::Low | Resolution::TwoPage => 64,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self.resolution {
+            Resolution::Low => 32,
+            Resolution::High | Resolution::TwoPage => 64,
+        }
+    }
+
+    /// Whether the SUPER-CHIP 128x64 mode is on.
+    pub fn is_high_resolution(&self) -> bool {
+        self.resolution == Resolution::High
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Switches between modes, as 00FE and 00FF do for the 64x32 and 128x64 ones.
+    /// Every plane is cleared, even when the resolution doesn't change.
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        self.planes = [[0; 64]; 2];
+    }
+
+    /// Whether the pixel is lit in any plane. Pixels outside the current resolution,
+    /// `x >= width()` or `y >= height()`, are never lit.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.color_index(x, y) != 0
     }
 
-    pub(crate) fn set_pixel(&mut self, x: u8, y: u8) -> bool {
-        let index = (y as usize * 64) + x as usize;
-        let collision = self.0[index];
-        self.0[index] ^= true;
+    /// Returns the pixel's color index, bit `p` being set if the pixel is lit in plane `p`.
+    /// Pixels outside the current resolution have index 0.
+    pub fn color_index(&self, x: usize, y: usize) -> u8 {
+        if x >= self.width() || y >= self.height() {
+            return 0;
+        }
+        self.planes
+            .iter()
+            .enumerate()
+            .fold(0, |index, (plane, rows)| {
+                index | (((rows[y] >> (127 - x)) & 1) as u8) << plane
+            })
+    }
+
+    /// Iterates over the visible rows, top first. Each row has a bit set for every
+    /// pixel lit in any plane, the most significant bit being the leftmost pixel.
+    pub fn rows(&self) -> impl Iterator<Item = u128> + '_ {
+        (0..self.height()).map(|y| self.planes[0][y] | self.planes[1][y])
+    }
+
+    /// Raw bit planes, indexed by plane then row, each row packed as in [`Screen::rows`].
+    pub fn as_bits(&self) -> &[[u128; 64]; 2] {
+        &self.planes
+    }
+
+    /// Returns a mask of the rows that differ from `previous`, bit `y` being set for row `y`.
+    pub fn changed_rows(&self, previous: &Screen) -> u64 {
+        (0..64)
+            .filter(|&y| {
+                self.planes
+                    .iter()
+                    .zip(previous.planes.iter())
+                    .any(|(rows, previous_rows)| rows[y] != previous_rows[y])
+            })
+            .fold(0, |mask, y| mask | 1 << y)
+    }
+
+    /// Hash of the resolution and every plane, stable across platforms and releases.
+    pub fn hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.write_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub(crate) fn write_hash(&self, hasher: &mut StableHasher) {
+        hasher.write(&[self.resolution as u8, self.selected_planes]);
+        for row in self.planes.iter().flatten() {
+            hasher.write(&row.to_le_bytes());
+        }
+    }
+
+    pub fn display(&self, format: ScreenFormat) -> ScreenDisplay<'_> {
+        ScreenDisplay {
+            screen: self,
+            format,
+        }
+    }
+
+    pub fn compact(&self) -> ScreenDisplay<'_> {
+        self.display(ScreenFormat {
+            glyphs: Glyphs::HalfBlock,
+            double_width: false,
+            border: true,
+        })
+    }
+
+    /// Returns the screen as RGBA bytes, row by row.
+    pub fn to_rgba(&self, palette: &Palette) -> Vec<u8> {
+        let mut buffer = vec![0; self.width() * self.height() * 4];
+        self.write_rgba(palette, &mut buffer);
+        buffer
+    }
+
+    /// Writes the screen as RGBA bytes into an existing buffer of `width * height * 4` bytes.
+    pub fn write_rgba(&self, palette: &Palette, buffer: &mut [u8]) {
+        let (width, height) = (self.width(), self.height());
+        assert_eq!(buffer.len(), width * height * 4, "RGBA buffer size");
+
+        // Walk each row's plane words from the leftmost bit instead of looking up every pixel
+        for (y, line) in buffer.chunks_exact_mut(width * 4).enumerate() {
+            let [mut low, mut high] = [self.planes[0][y], self.planes[1][y]];
+            if low | high == 0 {
+                for pixel in line.chunks_exact_mut(4) {
+                    pixel.copy_from_slice(&palette.0[0]);
+                }
+                continue;
+            }
+            for pixel in line.chunks_exact_mut(4) {
+                let index = (low >> 127) as usize | ((high >> 127) as usize) << 1;
+                pixel.copy_from_slice(&palette.0[index]);
+                low <<= 1;
+                high <<= 1;
+            }
+        }
+    }
+
+    // Mask of the bits of a row that are on screen
+    fn row_mask(&self) -> u128 {
+        !0 << (128 - self.width())
+    }
+
+    /// Draws a sprite line whose leftmost pixel is the most significant bit into a plane.
+    /// Pixels past the right edge are clipped, or wrapped around to the left edge.
+    pub(crate) fn draw_sprite_line(
+        &mut self,
+        plane: usize,
+        x: usize,
+        y: usize,
+        sprite_line: u128,
+        wrap: bool,
+    ) -> bool {
+        let mut shifted_line = sprite_line >> x;
+        if wrap {
+            // Bring the pixels that went past the right edge back to column 0
+            let wrapped = sprite_line.checked_shl((self.width() - x) as u32);
+            shifted_line |= wrapped.unwrap_or(0);
+        }
+        let sprite_line = shifted_line & self.row_mask();
+        let row = &mut self.planes[plane][y];
+
+        // Any pixel lit in both the row and the sprite line is turned off
+        let collision = *row & sprite_line != 0;
+        *row ^= sprite_line;
         collision
     }
+
+    pub(crate) fn scroll_down(&mut self, n: usize) {
+        let height = self.height();
+        let n = n.min(height);
+        for rows in self.selected_planes_mut() {
+            rows.copy_within(..height - n, n);
+            rows[..n].fill(0);
+        }
+    }
+
+    // Horizontal scrolls shift whole rows, and rows below the visible ones stay blank
+    pub(crate) fn scroll_right(&mut self, n: usize) {
+        let (height, mask) = (self.height(), self.row_mask());
+        for rows in self.selected_planes_mut() {
+            for row in &mut rows[..height] {
+                *row = row.checked_shr(n as u32).unwrap_or(0) & mask;
+            }
+        }
+    }
+
+    pub(crate) fn scroll_left(&mut self, n: usize) {
+        let height = self.height();
+        for rows in self.selected_planes_mut() {
+            for row in &mut rows[..height] {
+                *row = row.checked_shl(n as u32).unwrap_or(0);
+            }
+        }
+    }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Timer {
     pub value: u8,
     pub state: TimerState,
@@ -163,11 +731,16 @@ impl Timer {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TimerState {
     Zero,
     AboveZero,
 }
 
+// Address of the large SUPER-CHIP font, right after the small one
+pub const BIG_FONT_ADDRESS: u16 = 0x50;
+
 #[rustfmt::skip]
 pub const FONT_DATA: [u8; 80] = [
     // 0
@@ -282,3 +855,24 @@ pub const FONT_DATA: [u8; 80] = [
     0b10000000, 
     0b10000000,
 ];
+
+// Large 8x10 font, SUPER-CHIP only defines the digits, A to F come from later interpreters
+#[rustfmt::skip]
+pub const BIG_FONT_DATA: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
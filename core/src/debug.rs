@@ -0,0 +1,375 @@
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    string::ToString,
+    vec::Vec,
+};
+use core::{ops::Range, str::FromStr};
+
+use crate::{
+    core::Screen,
+    error::ConditionError,
+    instructions::{decode, Instruction},
+    interpreter::Interpreter,
+    savestate::Snapshot,
+};
+
+const NEXT_INSTRUCTIONS: usize = 8;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DecodedInstruction {
+    pub address: u16,
+    pub opcode: u16,
+    // `None` if the opcode is invalid
+    pub instruction: Option<Instruction>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Register(usize),
+    Index,
+    ProgramCounter,
+    DelayTimer,
+    SoundTimer,
+    Value(u16),
+}
+
+impl Operand {
+    fn value(&self, interpreter: &Interpreter) -> u16 {
+        match *self {
+            Operand::Register(register) => interpreter.variable_registers[register] as u16,
+            Operand::Index => interpreter.index_register,
+            Operand::ProgramCounter => interpreter.program_counter,
+            Operand::DelayTimer => interpreter.delay_timer.value as u16,
+            Operand::SoundTimer => interpreter.sound_timer.value as u16,
+            Operand::Value(value) => value,
+        }
+    }
+}
+
+/// Parses `V0`-`VF`, `I`, `PC`, `DT`, `ST`, or a decimal or `0x` hexadecimal value.
+impl FromStr for Operand {
+    type Err = ConditionError;
+
+    fn from_str(operand: &str) -> Result<Self, Self::Err> {
+        let unknown = || ConditionError::UnknownOperand(operand.to_string());
+        let upper = operand.to_ascii_uppercase();
+
+        Ok(match upper.as_str() {
+            "I" => Operand::Index,
+            "PC" => Operand::ProgramCounter,
+            "DT" => Operand::DelayTimer,
+            "ST" => Operand::SoundTimer,
+            _ => match (upper.strip_prefix('V'), upper.strip_prefix("0X")) {
+                (Some(register), _) if register.len() == 1 => {
+                    Operand::Register(usize::from_str_radix(register, 16).map_err(|_| unknown())?)
+                }
+                (_, Some(hex)) => {
+                    Operand::Value(u16::from_str_radix(hex, 16).map_err(|_| unknown())?)
+                }
+                _ => Operand::Value(upper.parse().map_err(|_| unknown())?),
+            },
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// A comparison between two operands, such as `V3 == 0x1F` or `I >= 0x300`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Condition {
+    pub left: Operand,
+    pub comparison: Comparison,
+    pub right: Operand,
+}
+
+impl Condition {
+    pub fn evaluate(&self, interpreter: &Interpreter) -> bool {
+        let (left, right) = (self.left.value(interpreter), self.right.value(interpreter));
+        match self.comparison {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = ConditionError;
+
+    fn from_str(condition: &str) -> Result<Self, Self::Err> {
+        // Two-character operators first, so `<=` isn't read as `<`
+        const OPERATORS: [(&str, Comparison); 6] = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+
+        let (left, comparison, right) = OPERATORS
+            .iter()
+            .find_map(|&(operator, comparison)| {
+                let (left, right) = condition.split_once(operator)?;
+                Some((left, comparison, right))
+            })
+            .ok_or(ConditionError::MissingComparison)?;
+
+        Ok(Self {
+            left: left.trim().parse()?,
+            comparison,
+            right: right.trim().parse()?,
+        })
+    }
+}
+
+/// Breakpoints attached to an interpreter through `Interpreter::debugger`.
+///
+/// Execution stops before running the instruction at a breakpoint whose condition,
+/// if any, holds, or before any instruction when a watch condition holds. The
+/// interpreter then reports `Status::BreakpointHit`, and the next step runs that
+/// instruction.
+///
+/// With a history capacity set, the state before each instruction is kept so that
+/// `Interpreter::step_back` can undo them.
+#[derive(Clone, Debug, Default)]
+pub struct Debugger {
+    breakpoints: BTreeMap<u16, Option<Condition>>,
+    // Checked before every instruction, wherever it is
+    watches: Vec<Condition>,
+    // Breakpoint execution is stopped at, cleared by the next step
+    stopped_at: Option<u16>,
+    // Snapshots taken before each instruction, oldest first
+    history: VecDeque<Snapshot>,
+    history_capacity: usize,
+    // Screen as of the last `debug_view`, to report what changed since
+    viewed_screen: Option<Screen>,
+}
+
+impl Debugger {
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address, None);
+    }
+
+    /// Adds a breakpoint that only stops when `condition` holds.
+    pub fn add_conditional_breakpoint(&mut self, address: u16, condition: Condition) {
+        self.breakpoints.insert(address, Some(condition));
+    }
+
+    /// Returns whether there was a breakpoint at the address.
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address).is_some()
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.watches.clear();
+    }
+
+    pub fn has_breakpoint(&self, address: u16) -> bool {
+        self.breakpoints.contains_key(&address)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (u16, Option<Condition>)> + '_ {
+        self.breakpoints
+            .iter()
+            .map(|(&address, &condition)| (address, condition))
+    }
+
+    /// Stops before every instruction run while `condition` holds.
+    pub fn add_watch(&mut self, condition: Condition) {
+        self.watches.push(condition);
+    }
+
+    pub fn watches(&self) -> &[Condition] {
+        &self.watches
+    }
+
+    pub fn stopped_at(&self) -> Option<u16> {
+        self.stopped_at
+    }
+
+    /// Keeps the state before each of the last `capacity` instructions, about 6 KB
+    /// each with 4 KiB of RAM. Zero turns the history off.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// Number of instructions that can be stepped back over.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub(crate) fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    fn is_triggered(&self, interpreter: &Interpreter) -> bool {
+        let address = interpreter.program_counter;
+        let breakpoint = match self.breakpoints.get(&address) {
+            Some(Some(condition)) => condition.evaluate(interpreter),
+            Some(None) => true,
+            None => false,
+        };
+        breakpoint
+            || self
+                .watches
+                .iter()
+                .any(|condition| condition.evaluate(interpreter))
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.stopped_at = None;
+    }
+}
+
+/// A watch condition along with the current values of its operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchValue {
+    pub condition: Condition,
+    pub left: u16,
+    pub right: u16,
+    pub holds: bool,
+}
+
+/// Snapshot of the machine state for debugger UIs.
+#[derive(Clone, Debug)]
+pub struct DebugView {
+    pub program_counter: u16,
+    pub index_register: u16,
+    pub registers: [u8; 16],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    // Return addresses, from the bottom of the stack to the top
+    pub stack: Vec<u16>,
+    // Instructions laid out linearly from the program counter
+    pub next_instructions: Vec<DecodedInstruction>,
+    pub watches: Vec<WatchValue>,
+    // Bands of rows that changed since the previous view, each spanning the whole width.
+    // The first view marks the whole screen.
+    pub dirty_regions: Vec<Range<usize>>,
+    pub waiting_for_key: Option<usize>,
+    pub paused: bool,
+    pub cycles: u64,
+    pub frames: u64,
+}
+
+// Groups the set bits of a row mask into ranges of consecutive rows
+fn row_ranges(mask: u64, height: usize) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for y in (0..height).filter(|&y| mask & 1 << y != 0) {
+        match ranges.last_mut() {
+            Some(range) if range.end == y => range.end += 1,
+            _ => ranges.push(y..y + 1),
+        }
+    }
+    ranges
+}
+
+impl Interpreter {
+    /// Gathers the state a debugger UI shows, and remembers the screen so the next
+    /// view only reports the rows drawn in between.
+    pub fn debug_view(&mut self) -> DebugView {
+        let next_instructions = (0..NEXT_INSTRUCTIONS as u16)
+            .map_while(|offset| {
+                let address = self.program_counter.checked_add(offset * 2)?;
+                let start = address as usize;
+                let &[high, low] = self.ram.get(start..start + 2)? else {
+                    return None;
+                };
+                let opcode = u16::from_be_bytes([high, low]);
+
+                Some(DecodedInstruction {
+                    address,
+                    opcode,
+                    instruction: decode(opcode).ok(),
+                })
+            })
+            .collect();
+
+        let watches = self
+            .debugger
+            .watches
+            .iter()
+            .map(|&condition| WatchValue {
+                condition,
+                left: condition.left.value(self),
+                right: condition.right.value(self),
+                holds: condition.evaluate(self),
+            })
+            .collect();
+
+        let changed_rows = match &self.debugger.viewed_screen {
+            Some(previous) if previous.width() == self.screen.width() => {
+                self.screen.changed_rows(previous)
+            }
+            _ => u64::MAX,
+        };
+        let dirty_regions = row_ranges(changed_rows, self.screen.height());
+        self.debugger.viewed_screen = Some(self.screen.clone());
+
+        DebugView {
+            program_counter: self.program_counter,
+            index_register: self.index_register,
+            registers: core::array::from_fn(|register| self.variable_registers[register]),
+            delay_timer: self.delay_timer.value,
+            sound_timer: self.sound_timer.value,
+            stack: self.stack.iter().collect(),
+            next_instructions,
+            watches,
+            dirty_regions,
+            waiting_for_key: self.input_handler.waiting,
+            paused: self.is_paused(),
+            cycles: self.cycles(),
+            frames: self.frames(),
+        }
+    }
+
+    /// Undoes the last instruction run, restoring the state from the debugger history.
+    /// Returns false once the history is exhausted.
+    pub fn step_back(&mut self) -> bool {
+        match self.debugger.history.pop_back() {
+            Some(snapshot) => self.restore_snapshot(&snapshot),
+            None => false,
+        }
+    }
+
+    // Called before each instruction runs
+    pub(crate) fn save_history(&mut self) {
+        if self.debugger.history_capacity == 0 {
+            return;
+        }
+        if self.debugger.history.len() == self.debugger.history_capacity {
+            self.debugger.history.pop_front();
+        }
+        let snapshot = self.snapshot();
+        self.debugger.history.push_back(snapshot);
+    }
+
+    // Stops once at each breakpoint reached, stepping again runs past it
+    pub(crate) fn should_stop(&mut self) -> bool {
+        let address = self.program_counter;
+        if self.debugger.stopped_at.take() == Some(address) {
+            return false;
+        }
+        if self.debugger.is_triggered(self) {
+            self.debugger.stopped_at = Some(address);
+            return true;
+        }
+        false
+    }
+}
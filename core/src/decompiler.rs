@@ -0,0 +1,519 @@
+use alloc::{
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::{
+    disassembler::{Analysis, DisasmLine, ReferenceKind},
+    instructions::Instruction,
+};
+
+// A statement, or a construct rebuilt from skips and jumps
+enum Node {
+    Statement {
+        address: u16,
+        text: String,
+    },
+    Loop {
+        address: u16,
+        body: Vec<Node>,
+    },
+    If {
+        address: u16,
+        condition: String,
+        body: Vec<Node>,
+        otherwise: Option<Vec<Node>>,
+    },
+}
+
+impl Node {
+    fn address(&self) -> u16 {
+        match *self {
+            Node::Statement { address, .. }
+            | Node::Loop { address, .. }
+            | Node::If { address, .. } => address,
+        }
+    }
+}
+
+struct Decompiler<'a> {
+    analysis: &'a Analysis,
+    origin: u16,
+    // Jumps turned into `again`, `begin` or `else`, which don't need their labels
+    consumed: BTreeSet<u16>,
+    emitted: BTreeSet<u16>,
+    out: String,
+}
+
+/// Rebuilds the code found by [`analyze`](crate::disassembler::analyze) as Octo-like
+/// pseudo-code, one `: name` block per routine with the registers it reads and writes.
+/// Backward jumps become `loop ... again`, skips over one instruction `if ... then`, and
+/// skips over a forward jump `if ... begin ... else ... end`. Jumps that don't fit these
+/// shapes are kept along with their labels. Data isn't listed.
+pub fn decompile(analysis: &Analysis) -> String {
+    let Some(origin) = analysis.lines.first().map(|line| line.address) else {
+        return String::new();
+    };
+    let code: Vec<DisasmLine> = analysis
+        .lines
+        .iter()
+        .filter(|line| line.instruction.is_some())
+        .copied()
+        .collect();
+
+    // Routines start where execution does and at every call target. Data between
+    // instructions ends a block too.
+    let mut blocks: Vec<&[DisasmLine]> = Vec::new();
+    let mut start = 0;
+    for i in 1..=code.len() {
+        let split = code.get(i).is_none_or(|line| {
+            let previous = &code[i - 1];
+            line.address != previous.address.wrapping_add(previous.size as u16)
+                || analysis
+                    .xrefs
+                    .get(&line.address)
+                    .is_some_and(|xrefs| xrefs.iter().any(|xref| xref.kind == ReferenceKind::Call))
+        });
+        if split {
+            blocks.push(&code[start..i]);
+            start = i;
+        }
+    }
+
+    let mut decompiler = Decompiler {
+        analysis,
+        origin,
+        consumed: BTreeSet::new(),
+        emitted: BTreeSet::new(),
+        out: String::new(),
+    };
+    let blocks: Vec<(&[DisasmLine], Vec<Node>)> = blocks
+        .into_iter()
+        .map(|lines| (lines, decompiler.structure(lines)))
+        .collect();
+
+    for (i, (lines, nodes)) in blocks.iter().enumerate() {
+        if i > 0 {
+            decompiler.out.push('\n');
+        }
+        let start = lines[0].address;
+        decompiler.emitted.insert(start);
+        let name = decompiler.name(start);
+        let _ = writeln!(decompiler.out, ": {name}");
+        decompiler.registers(lines);
+        decompiler.render(nodes, 1);
+    }
+
+    decompiler.out
+}
+
+impl Decompiler<'_> {
+    fn structure(&mut self, lines: &[DisasmLine]) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = &lines[i];
+            let address = line.address;
+
+            // The furthest jump back to here closes the loop
+            if let Some(end) = (i..lines.len())
+                .rev()
+                .find(|&j| plain_jump(lines, j) == Some(address))
+            {
+                self.consumed.insert(lines[end].address);
+                let body = self.structure(&lines[i..end]);
+                nodes.push(Node::Loop { address, body });
+                i = end + 1;
+                continue;
+            }
+
+            let Some((skipped_when, runs_when)) = conditions(line) else {
+                let text = self.statement(line);
+                nodes.push(Node::Statement { address, text });
+                i += 1;
+                continue;
+            };
+            let Some(next) = lines.get(i + 1) else {
+                // The skipped instruction isn't part of this block
+                let text = format!("# {}", line.instruction.unwrap());
+                nodes.push(Node::Statement { address, text });
+                i += 1;
+                continue;
+            };
+
+            // Skipping a jump over the following lines runs them only when the skip happens
+            let body_end = match next.instruction {
+                Some(Instruction::Jump { address: after }) => {
+                    position(lines, after).filter(|&end| end > i + 1)
+                }
+                _ => None,
+            };
+            let Some(body_end) = body_end else {
+                let text = format!("if {runs_when} then {}", self.statement(next));
+                nodes.push(Node::Statement { address, text });
+                i += 2;
+                continue;
+            };
+            self.consumed.insert(next.address);
+
+            let body = &lines[i + 2..body_end];
+            // A jump over the lines that follow ends the body when there's an else branch
+            let else_end = body.len().checked_sub(1).and_then(|last| {
+                let target = plain_jump(body, last)?;
+                position(lines, target).filter(|&end| end > body_end)
+            });
+            let node = match else_end {
+                Some(end) => {
+                    self.consumed.insert(body[body.len() - 1].address);
+                    let body = self.structure(&body[..body.len() - 1]);
+                    let otherwise = self.structure(&lines[body_end..end]);
+                    i = end;
+                    Node::If {
+                        address,
+                        condition: skipped_when,
+                        body,
+                        otherwise: Some(otherwise),
+                    }
+                }
+                None => {
+                    let body = self.structure(body);
+                    i = body_end;
+                    Node::If {
+                        address,
+                        condition: skipped_when,
+                        body,
+                        otherwise: None,
+                    }
+                }
+            };
+            nodes.push(node);
+        }
+
+        nodes
+    }
+
+    fn render(&mut self, nodes: &[Node], depth: usize) {
+        let indent = "  ".repeat(depth);
+        for node in nodes {
+            let address = node.address();
+            if self.needs_label(address) && self.emitted.insert(address) {
+                let name = self.name(address);
+                let _ = writeln!(self.out, "{indent}: {name}");
+            }
+            match node {
+                Node::Statement { text, .. } => {
+                    let _ = writeln!(self.out, "{indent}{text}");
+                }
+                Node::Loop { body, .. } => {
+                    let _ = writeln!(self.out, "{indent}loop");
+                    self.render(body, depth + 1);
+                    let _ = writeln!(self.out, "{indent}again");
+                }
+                Node::If {
+                    condition,
+                    body,
+                    otherwise,
+                    ..
+                } => {
+                    let _ = writeln!(self.out, "{indent}if {condition} begin");
+                    self.render(body, depth + 1);
+                    if let Some(otherwise) = otherwise {
+                        let _ = writeln!(self.out, "{indent}else");
+                        self.render(otherwise, depth + 1);
+                    }
+                    let _ = writeln!(self.out, "{indent}end");
+                }
+            }
+        }
+    }
+
+    // Registers used directly by a routine, not counting the routines it calls
+    fn registers(&mut self, lines: &[DisasmLine]) {
+        let (reads, writes) = lines
+            .iter()
+            .filter_map(|line| line.instruction)
+            .map(|instruction| register_usage(&instruction))
+            .fold((0u16, 0u16), |(reads, writes), (read, written)| {
+                (reads | read, writes | written)
+            });
+        let list = |mask: u16| {
+            (0..16)
+                .filter(|register| mask & (1 << register) != 0)
+                .map(|register| format!("v{register:x}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut parts = Vec::new();
+        if reads != 0 {
+            parts.push(format!("reads {}", list(reads)));
+        }
+        if writes != 0 {
+            parts.push(format!("writes {}", list(writes)));
+        }
+        if !parts.is_empty() {
+            let _ = writeln!(self.out, "  # {}", parts.join(", "));
+        }
+    }
+
+    // Labels are only kept for jumps that weren't rebuilt into a construct
+    fn needs_label(&self, address: u16) -> bool {
+        self.analysis.labels.contains_key(&address)
+            && self.analysis.xrefs.get(&address).is_some_and(|xrefs| {
+                xrefs.iter().any(|xref| {
+                    xref.kind == ReferenceKind::Jump && !self.consumed.contains(&xref.from)
+                })
+            })
+    }
+
+    fn name(&self, address: u16) -> String {
+        if address == self.origin {
+            return "main".to_string();
+        }
+        match self.analysis.labels.get(&address) {
+            Some(label) => label.clone(),
+            None => format!("{address:#05X}"),
+        }
+    }
+
+    fn statement(&self, line: &DisasmLine) -> String {
+        use Instruction::*;
+
+        // Only called on decoded lines
+        let Some(instruction) = line.instruction else {
+            return String::new();
+        };
+        match instruction {
+            Call { address }
+                if address == self.origin || self.analysis.labels.contains_key(&address) =>
+            {
+                self.name(address)
+            }
+            Call { address } => format!(":call {address:#05X}"),
+            Return => "return".to_string(),
+            Jump { address } => format!("jump {}", self.name(address)),
+            JumpOffset { base_address, .. } => format!("jump0 {}", self.name(base_address)),
+            SetWithByte { register, byte } => format!("v{register:x} := {byte}"),
+            SetWithVariable {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} := v{register_y:x}"),
+            SetIndexWithAddress { address } => format!("i := {address:#05X}"),
+            SetIndexWithFontAddress { register } => format!("i := hex v{register:x}"),
+            SetIndexWithBigFontAddress { register } => format!("i := bighex v{register:x}"),
+            SetIndexLong => format!("i := long {:#06X}", line.long_address.unwrap_or_default()),
+            AddWithByte { register, byte } => format!("v{register:x} += {byte}"),
+            AddWithVariable {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} += v{register_y:x}"),
+            AddIndexWithVariable { register } => format!("i += v{register:x}"),
+            SubWithVariable {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} -= v{register_y:x}"),
+            SubWithVariableNot {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} =- v{register_y:x}"),
+            ShiftRight {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} >>= v{register_y:x}"),
+            ShiftLeft {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} <<= v{register_y:x}"),
+            Or {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} |= v{register_y:x}"),
+            And {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} &= v{register_y:x}"),
+            Xor {
+                register_x,
+                register_y,
+            } => format!("v{register_x:x} ^= v{register_y:x}"),
+            ClearScreen => "clear".to_string(),
+            Draw {
+                register_x,
+                register_y,
+                n,
+            } => format!("sprite v{register_x:x} v{register_y:x} {n}"),
+            ScrollDown { n } => format!("scroll-down {n}"),
+            ScrollRight => "scroll-right".to_string(),
+            ScrollLeft => "scroll-left".to_string(),
+            LowResolution => "lores".to_string(),
+            HighResolution => "hires".to_string(),
+            SelectPlanes { mask } => format!("plane {mask}"),
+            SetVariableWithDelayTimer { register } => format!("v{register:x} := delay"),
+            SetDelayTimer { register } => format!("delay := v{register:x}"),
+            SetSoundTimer { register } => format!("buzzer := v{register:x}"),
+            SetPitch { register } => format!("pitch := v{register:x}"),
+            StoreRegisters { up_to_register } => format!("save v{up_to_register:x}"),
+            LoadIntoRegisters { up_to_register } => format!("load v{up_to_register:x}"),
+            StoreFlags { up_to_register } => format!("saveflags v{up_to_register:x}"),
+            LoadFlags { up_to_register } => format!("loadflags v{up_to_register:x}"),
+            LoadAudioPattern => "audio".to_string(),
+            StoreDecimalConversion { register } => format!("bcd v{register:x}"),
+            WaitForKey { register } => format!("v{register:x} := key"),
+            RandomAnd { register, byte } => format!("v{register:x} := random {byte:#04X}"),
+            Exit => "exit".to_string(),
+            MachineRoutine { address } => format!("native {address:#05X}"),
+            // Skips are folded into the following statement
+            SkipEqualByte { .. }
+            | SkipNotEqualByte { .. }
+            | SkipEqualVariable { .. }
+            | SkipNotEqualVariable { .. }
+            | SkipKey { .. }
+            | SkipNotKey { .. } => format!("# {instruction}"),
+        }
+    }
+}
+
+// Target of an unconditional jump that isn't the instruction a skip guards
+fn plain_jump(lines: &[DisasmLine], index: usize) -> Option<u16> {
+    let guarded = index
+        .checked_sub(1)
+        .is_some_and(|previous| conditions(&lines[previous]).is_some());
+    match lines[index].instruction {
+        Some(Instruction::Jump { address }) if !guarded => Some(address),
+        _ => None,
+    }
+}
+
+// Index of the line at `address`, or the end of `lines` when it's right after them
+fn position(lines: &[DisasmLine], address: u16) -> Option<usize> {
+    let last = lines.last()?;
+    if address == last.address.wrapping_add(last.size as u16) {
+        return Some(lines.len());
+    }
+    lines.iter().position(|line| line.address == address)
+}
+
+// For a skip: when it skips, and when the next instruction runs instead
+fn conditions(line: &DisasmLine) -> Option<(String, String)> {
+    let (skipped_when, runs_when) = match line.instruction? {
+        Instruction::SkipEqualByte { register, byte } => (
+            format!("v{register:x} == {byte}"),
+            format!("v{register:x} != {byte}"),
+        ),
+        Instruction::SkipNotEqualByte { register, byte } => (
+            format!("v{register:x} != {byte}"),
+            format!("v{register:x} == {byte}"),
+        ),
+        Instruction::SkipEqualVariable {
+            register_x,
+            register_y,
+        } => (
+            format!("v{register_x:x} == v{register_y:x}"),
+            format!("v{register_x:x} != v{register_y:x}"),
+        ),
+        Instruction::SkipNotEqualVariable {
+            register_x,
+            register_y,
+        } => (
+            format!("v{register_x:x} != v{register_y:x}"),
+            format!("v{register_x:x} == v{register_y:x}"),
+        ),
+        Instruction::SkipKey { register } => {
+            (format!("v{register:x} key"), format!("v{register:x} -key"))
+        }
+        Instruction::SkipNotKey { register } => {
+            (format!("v{register:x} -key"), format!("v{register:x} key"))
+        }
+        _ => return None,
+    };
+    Some((skipped_when, runs_when))
+}
+
+// Registers read and written by an instruction, as bit masks
+fn register_usage(instruction: &Instruction) -> (u16, u16) {
+    use Instruction::*;
+
+    let bit = |register: usize| 1u16 << register;
+    let up_to = |register: usize| (bit(register) << 1).wrapping_sub(1);
+    const VF: u16 = 1 << 0xF;
+
+    match *instruction {
+        SetWithByte { register, .. }
+        | SetVariableWithDelayTimer { register }
+        | WaitForKey { register }
+        | RandomAnd { register, .. } => (0, bit(register)),
+        SetWithVariable {
+            register_x,
+            register_y,
+        } => (bit(register_y), bit(register_x)),
+        AddWithByte { register, .. } => (bit(register), bit(register)),
+        Or {
+            register_x,
+            register_y,
+        }
+        | And {
+            register_x,
+            register_y,
+        }
+        | Xor {
+            register_x,
+            register_y,
+        } => (bit(register_x) | bit(register_y), bit(register_x)),
+        AddWithVariable {
+            register_x,
+            register_y,
+        }
+        | SubWithVariable {
+            register_x,
+            register_y,
+        }
+        | SubWithVariableNot {
+            register_x,
+            register_y,
+        }
+        | ShiftRight {
+            register_x,
+            register_y,
+        }
+        | ShiftLeft {
+            register_x,
+            register_y,
+        } => (bit(register_x) | bit(register_y), bit(register_x) | VF),
+        SkipEqualVariable {
+            register_x,
+            register_y,
+        }
+        | SkipNotEqualVariable {
+            register_x,
+            register_y,
+        } => (bit(register_x) | bit(register_y), 0),
+        Draw {
+            register_x,
+            register_y,
+            ..
+        } => (bit(register_x) | bit(register_y), VF),
+        SkipEqualByte { register, .. }
+        | SkipNotEqualByte { register, .. }
+        | SkipKey { register }
+        | SkipNotKey { register }
+        | JumpOffset { register, .. }
+        | SetIndexWithFontAddress { register }
+        | SetIndexWithBigFontAddress { register }
+        | AddIndexWithVariable { register }
+        | SetDelayTimer { register }
+        | SetSoundTimer { register }
+        | SetPitch { register }
+        | StoreDecimalConversion { register } => (bit(register), 0),
+        StoreRegisters { up_to_register } | StoreFlags { up_to_register } => {
+            (up_to(up_to_register), 0)
+        }
+        LoadIntoRegisters { up_to_register } | LoadFlags { up_to_register } => {
+            (0, up_to(up_to_register))
+        }
+        _ => (0, 0),
+    }
+}
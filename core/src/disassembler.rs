@@ -0,0 +1,245 @@
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+use core::fmt::Display;
+
+use crate::instructions::{decode, Instruction};
+
+/// One instruction, or one byte of data, of a disassembled program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisasmLine {
+    pub address: u16,
+    // Bytes covered: 2 for most instructions, 4 for F000 nnnn, 1 for a trailing odd byte
+    pub size: u8,
+    // Raw opcode, or the lone byte when `size` is 1
+    pub opcode: u16,
+    // `None` for invalid opcodes and trailing bytes
+    pub instruction: Option<Instruction>,
+    // Address loaded by F000 nnnn, read from the word after the opcode
+    pub long_address: Option<u16>,
+}
+
+impl Display for DisasmLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#05X}  ", self.address)?;
+        match (self.instruction, self.long_address) {
+            (Some(_), Some(address)) => write!(f, "{:04X}  LD I, {address:#06X}", self.opcode),
+            (Some(instruction), None) => write!(f, "{:04X}  {instruction}", self.opcode),
+            (None, _) if self.size == 1 => {
+                write!(f, "{:02X}    DB {:#04X}", self.opcode, self.opcode)
+            }
+            (None, _) => write!(f, "{:04X}  DW {:#06X}", self.opcode, self.opcode),
+        }
+    }
+}
+
+/// Decodes `program` linearly, as if loaded at `origin`. Data mixed in with the code
+/// is decoded as instructions too, see [`analyze`] to tell them apart.
+pub fn disassemble(program: &[u8], origin: u16) -> Vec<DisasmLine> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    while offset < program.len() {
+        let line = decode_at(program, offset, origin);
+        offset += line.size as usize;
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// How an instruction refers to an address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReferenceKind {
+    Jump,
+    Call,
+    // Index register loads, usually pointing at sprite data
+    Index,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Xref {
+    pub from: u16,
+    pub kind: ReferenceKind,
+}
+
+/// Result of [`analyze`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    // Reachable instructions, with everything else as single `DB` bytes
+    pub lines: Vec<DisasmLine>,
+    // Jump and call targets inside the program: `sub_XXX` for calls, `label_XXX` otherwise
+    pub labels: BTreeMap<u16, String>,
+    // Every address referenced by a jump, call or index load, with where from
+    pub xrefs: BTreeMap<u16, Vec<Xref>>,
+}
+
+impl Analysis {
+    pub fn is_code(&self, address: u16) -> bool {
+        self.lines
+            .iter()
+            .any(|line| line.instruction.is_some() && line.address == address)
+    }
+}
+
+/// One line per instruction or data byte, with labels and the callers of each label.
+impl Display for Analysis {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for line in &self.lines {
+            if let Some(label) = self.labels.get(&line.address) {
+                write!(f, "{label}:")?;
+                let xrefs = self.xrefs.get(&line.address).into_iter().flatten();
+                for (i, xref) in xrefs.enumerate() {
+                    let separator = if i == 0 { "  ; from" } else { "," };
+                    write!(f, "{separator} {:#05X}", xref.from)?;
+                }
+                writeln!(f)?;
+            }
+            match line
+                .instruction
+                .and_then(|instruction| target(&instruction))
+            {
+                Some((address, _)) if self.labels.contains_key(&address) => {
+                    writeln!(f, "{line}  ; {}", self.labels[&address])?
+                }
+                _ => writeln!(f, "{line}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Follows every path from `origin`, where execution starts, to separate code from
+/// data. Computed jumps (`JP V0, nnn`) and self-modifying code can't be followed, so
+/// what they reach is left as data.
+pub fn analyze(program: &[u8], origin: u16) -> Analysis {
+    let mut code_starts = BTreeMap::new();
+    let mut labels = BTreeMap::new();
+    let mut xrefs: BTreeMap<u16, Vec<Xref>> = BTreeMap::new();
+    let mut pending = vec![origin];
+
+    let offset_of = |address: u16| {
+        let offset = address.wrapping_sub(origin) as usize;
+        (offset < program.len()).then_some(offset)
+    };
+
+    while let Some(address) = pending.pop() {
+        let Some(offset) = offset_of(address) else {
+            continue;
+        };
+        if code_starts.contains_key(&address) {
+            continue;
+        }
+        let line = decode_at(program, offset, origin);
+        let Some(instruction) = line.instruction else {
+            continue;
+        };
+        code_starts.insert(address, line);
+
+        let next = address.wrapping_add(line.size as u16);
+        if let Some((target, kind)) = target(&instruction) {
+            xrefs.entry(target).or_default().push(Xref {
+                from: address,
+                kind,
+            });
+            if offset_of(target).is_some() {
+                match kind {
+                    ReferenceKind::Call => {
+                        labels.insert(target, format!("sub_{target:03X}"));
+                    }
+                    ReferenceKind::Jump => {
+                        labels
+                            .entry(target)
+                            .or_insert_with(|| format!("label_{target:03X}"));
+                    }
+                    ReferenceKind::Index => {}
+                }
+            }
+        }
+
+        match instruction {
+            Instruction::Jump { address } => pending.push(address),
+            Instruction::Call { address } => pending.extend([next, address]),
+            Instruction::Return | Instruction::Exit | Instruction::JumpOffset { .. } => {}
+            Instruction::SkipEqualByte { .. }
+            | Instruction::SkipNotEqualByte { .. }
+            | Instruction::SkipEqualVariable { .. }
+            | Instruction::SkipNotEqualVariable { .. }
+            | Instruction::SkipKey { .. }
+            | Instruction::SkipNotKey { .. } => {
+                // F000 nnnn is skipped as a whole
+                let skipped = match offset_of(next).map(|offset| &program[offset..]) {
+                    #[cfg(feature = "xochip")]
+                    Some([0xF0, 0x00, ..]) => 4,
+                    _ => 2,
+                };
+                pending.extend([next.wrapping_add(skipped), next]);
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    // Overlapping instructions can't both be listed, the first one wins
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < program.len() {
+        let address = origin.wrapping_add(offset as u16);
+        let line = code_starts.get(&address).copied().unwrap_or(DisasmLine {
+            address,
+            size: 1,
+            opcode: program[offset] as u16,
+            instruction: None,
+            long_address: None,
+        });
+        offset += line.size as usize;
+        lines.push(line);
+    }
+
+    Analysis {
+        lines,
+        labels,
+        xrefs,
+    }
+}
+
+// Address an instruction refers to, if it's known statically
+fn target(instruction: &Instruction) -> Option<(u16, ReferenceKind)> {
+    match *instruction {
+        Instruction::Jump { address } => Some((address, ReferenceKind::Jump)),
+        Instruction::JumpOffset { base_address, .. } => Some((base_address, ReferenceKind::Jump)),
+        Instruction::Call { address } => Some((address, ReferenceKind::Call)),
+        Instruction::SetIndexWithAddress { address } => Some((address, ReferenceKind::Index)),
+        _ => None,
+    }
+}
+
+fn decode_at(program: &[u8], offset: usize, origin: u16) -> DisasmLine {
+    let address = origin.wrapping_add(offset as u16);
+    match program[offset..] {
+        [high, low, ref rest @ ..] => {
+            let opcode = u16::from_be_bytes([high, low]);
+            let (instruction, long_address) = match (decode(opcode).ok(), rest) {
+                (Some(Instruction::SetIndexLong), [high, low, ..]) => (
+                    Some(Instruction::SetIndexLong),
+                    Some(u16::from_be_bytes([*high, *low])),
+                ),
+                // F000 cut off before its address word
+                (Some(Instruction::SetIndexLong), _) => (None, None),
+                (instruction, _) => (instruction, None),
+            };
+            DisasmLine {
+                address,
+                size: if long_address.is_some() { 4 } else { 2 },
+                opcode,
+                instruction,
+                long_address,
+            }
+        }
+        [byte] => DisasmLine {
+            address,
+            size: 1,
+            opcode: byte as u16,
+            instruction: None,
+            long_address: None,
+        },
+        [] => unreachable!(),
+    }
+}
@@ -0,0 +1,119 @@
+use alloc::string::String;
+
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecodeError {
+    #[error("invalid opcode ({0:04X})")]
+    InvalidOpcode(u16),
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("program is empty")]
+    Empty,
+    #[error("program is too large ({size} bytes, the maximum is {max} bytes)")]
+    TooLarge { size: usize, max: usize },
+    #[error("segment of {size} bytes at {address:#05X} does not fit in memory")]
+    SegmentOutOfBounds { address: u16, size: usize },
+    #[error("bank {bank} does not have the same size as the first bank")]
+    BankSizeMismatch { bank: usize },
+    #[error("bank {bank} does not exist")]
+    InvalidBank { bank: usize },
+    #[error("memory size of {size} bytes is not between 513 bytes and 64 KiB")]
+    InvalidMemorySize { size: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum PatchError {
+    #[error("patch does not start with an IPS or BPS header")]
+    InvalidHeader,
+    #[error("patch ends in the middle of a record")]
+    Truncated,
+    #[error("ROM is too large for an IPS patch ({size} bytes)")]
+    TooLarge { size: usize },
+    #[error("patch is corrupted (checksum mismatch)")]
+    ChecksumMismatch,
+    #[error("patch was made for a different ROM")]
+    SourceMismatch,
+    #[error("patched ROM does not match the patch's checksum")]
+    TargetMismatch,
+    #[error("patch reads or writes outside the ROM")]
+    OutOfBounds,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ConditionError {
+    #[error("expected a comparison such as `V3 == 0x1F`")]
+    MissingComparison,
+    #[error("unknown operand `{0}`")]
+    UnknownOperand(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum LayoutError {
+    #[error("unknown keyboard layout `{0}`, expected qwerty, azerty, qwertz, dvorak or colemak")]
+    Unknown(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ScoreLocationError {
+    #[error("expected a score location such as `0x2F0:3:bcd`")]
+    InvalidFormat,
+    #[error("unknown score encoding `{0}`, expected bcd, packed or binary")]
+    UnknownEncoding(String),
+}
+
+#[derive(Debug, Error)]
+pub enum BatteryError {
+    #[error("expected a battery range such as `0x2F0:16`")]
+    InvalidFormat,
+    #[error("battery save holds {size} bytes, the range holds {expected}")]
+    SizeMismatch { size: usize, expected: usize },
+    #[error(transparent)]
+    Load(#[from] LoadError),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ReplayError {
+    #[error("playback no longer matches the recording at frame {frame}")]
+    Desync { frame: u64 },
+}
+
+#[derive(Debug, Error)]
+pub enum SaveStateError {
+    #[error("data is not a save state")]
+    InvalidMagic,
+    #[error("save state version {version} is not supported")]
+    UnsupportedVersion { version: u16 },
+    #[error("save state is corrupted (checksum mismatch)")]
+    ChecksumMismatch,
+    #[error("save state ends unexpectedly")]
+    Truncated,
+    #[error("save state holds an invalid {field}")]
+    InvalidValue { field: &'static str },
+    #[error(transparent)]
+    Load(#[from] LoadError),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InterpreterError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error("return with an empty stack at {address:#05X}")]
+    StackUnderflow { address: u16 },
+    #[error("call with a full stack at {address:#05X}")]
+    StackOverflow { address: u16 },
+    #[error("program counter out of bounds ({address:#05X})")]
+    ProgramCounterOutOfBounds { address: u16 },
+    #[error("memory access out of bounds ({address:#05X})")]
+    OutOfBoundsAccess { address: u16 },
+    #[error("write to reserved memory ({address:#05X})")]
+    ProtectedWrite { address: u16 },
+    #[error("instruction at {pc:#05X} writes to executed code ({address:#05X})")]
+    SelfModifyingWrite { pc: u16, address: u16 },
+    #[error("execution aborted by a hook at {address:#05X}")]
+    Aborted { address: u16 },
+}
@@ -0,0 +1,64 @@
+#[cfg(feature = "std")]
+use std::{fs, path::PathBuf};
+
+/// Backing store for the SUPER-CHIP flag registers saved by Fx75 and restored by Fx85.
+///
+/// SUPER-CHIP only uses the first 8 flags, XO-CHIP programs may use all 16.
+pub trait FlagStorage {
+    fn load(&mut self) -> [u8; 16];
+    fn save(&mut self, flags: &[u8; 16]);
+}
+
+/// Flags kept in memory, lost when the interpreter is dropped.
+#[derive(Clone, Copy, Default)]
+pub struct MemoryFlags(pub [u8; 16]);
+
+impl FlagStorage for MemoryFlags {
+    fn load(&mut self) -> [u8; 16] {
+        self.0
+    }
+
+    fn save(&mut self, flags: &[u8; 16]) {
+        self.0 = *flags;
+    }
+}
+
+/// Flags persisted to a file, so they survive across sessions.
+///
+/// A missing or unreadable file reads as all zeroes, and failed writes are
+/// ignored: the flags are only ever a convenience for the program.
+#[cfg(feature = "std")]
+pub struct FileFlags {
+    path: PathBuf,
+    flags: MemoryFlags,
+}
+
+#[cfg(feature = "std")]
+impl FileFlags {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut flags = [0; 16];
+        if let Ok(saved) = fs::read(&path) {
+            for (flag, value) in flags.iter_mut().zip(saved) {
+                *flag = value;
+            }
+        }
+
+        Self {
+            path,
+            flags: MemoryFlags(flags),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FlagStorage for FileFlags {
+    fn load(&mut self) -> [u8; 16] {
+        self.flags.load()
+    }
+
+    fn save(&mut self, flags: &[u8; 16]) {
+        self.flags.save(flags);
+        let _ = fs::write(&self.path, flags);
+    }
+}
@@ -1,4 +1,11 @@
-pub fn decode(instruction: u16) -> Instruction {
+use core::fmt::Display;
+
+use crate::error::DecodeError;
+
+/// Decodes an opcode. SUPER-CHIP and XO-CHIP opcodes are only recognized with the
+/// `schip` and `xochip` features, otherwise they are invalid or, for 00Cn, 00FB-00FF,
+/// the classic machine routine.
+pub fn decode(instruction: u16) -> Result<Instruction, DecodeError> {
     use Instruction::*;
 
     let a = (instruction & 0xF000) >> 12;
@@ -6,9 +13,21 @@ pub fn decode(instruction: u16) -> Instruction {
     let c = (instruction & 0x00F0) >> 4;
     let d = instruction & 0x000F;
 
-    match (a, b, c, d) {
+    let decoded = match (a, b, c, d) {
         (0x0, 0x0, 0xE, 0x0) => ClearScreen,
         (0x0, 0x0, 0xE, 0xE) => Return,
+        #[cfg(feature = "schip")]
+        (0x0, 0x0, 0xC, _) => ScrollDown { n: d as u8 },
+        #[cfg(feature = "schip")]
+        (0x0, 0x0, 0xF, 0xB) => ScrollRight,
+        #[cfg(feature = "schip")]
+        (0x0, 0x0, 0xF, 0xC) => ScrollLeft,
+        #[cfg(feature = "schip")]
+        (0x0, 0x0, 0xF, 0xD) => Exit,
+        #[cfg(feature = "schip")]
+        (0x0, 0x0, 0xF, 0xE) => LowResolution,
+        #[cfg(feature = "schip")]
+        (0x0, 0x0, 0xF, 0xF) => HighResolution,
         (0x0, _, _, _) => MachineRoutine {
             address: instruction & 0x0FFF,
         },
@@ -64,7 +83,6 @@ pub fn decode(instruction: u16) -> Instruction {
         },
         (0x8, _, _, 0x6) => ShiftRight {
             register_x: b as usize,
-            #[cfg(not(feature = "modern"))]
             register_y: c as usize,
         },
         (0x8, _, _, 0x7) => SubWithVariableNot {
@@ -73,7 +91,6 @@ pub fn decode(instruction: u16) -> Instruction {
         },
         (0x8, _, _, 0xE) => ShiftLeft {
             register_x: b as usize,
-            #[cfg(not(feature = "modern"))]
             register_y: c as usize,
         },
         (0x9, _, _, 0x0) => SkipNotEqualVariable {
@@ -85,7 +102,6 @@ pub fn decode(instruction: u16) -> Instruction {
         },
         (0xB, _, _, _) => JumpOffset {
             base_address: instruction & 0x0FFF,
-            #[cfg(feature = "modern")]
             register: b as usize,
         },
         (0xC, _, _, _) => RandomAnd {
@@ -103,6 +119,12 @@ pub fn decode(instruction: u16) -> Instruction {
         (0xE, _, 0xA, 0x1) => SkipNotKey {
             register: b as usize,
         },
+        #[cfg(feature = "xochip")]
+        (0xF, 0x0, 0x0, 0x0) => SetIndexLong,
+        #[cfg(feature = "xochip")]
+        (0xF, _, 0x0, 0x1) => SelectPlanes { mask: b as u8 },
+        #[cfg(feature = "xochip")]
+        (0xF, 0x0, 0x0, 0x2) => LoadAudioPattern,
         (0xF, _, 0x0, 0x7) => SetVariableWithDelayTimer {
             register: b as usize,
         },
@@ -121,6 +143,14 @@ pub fn decode(instruction: u16) -> Instruction {
         (0xF, _, 0x2, 0x9) => SetIndexWithFontAddress {
             register: b as usize,
         },
+        #[cfg(feature = "schip")]
+        (0xF, _, 0x3, 0x0) => SetIndexWithBigFontAddress {
+            register: b as usize,
+        },
+        #[cfg(feature = "xochip")]
+        (0xF, _, 0x3, 0xA) => SetPitch {
+            register: b as usize,
+        },
         (0xF, _, 0x3, 0x3) => StoreDecimalConversion {
             register: b as usize,
         },
@@ -130,11 +160,21 @@ pub fn decode(instruction: u16) -> Instruction {
         (0xF, _, 0x6, 0x5) => LoadIntoRegisters {
             up_to_register: b as usize,
         },
-        _ => unreachable!("invalid opcode ({a:01X}{b:01X}{c:01X}{d:01X})"),
-    }
+        #[cfg(feature = "schip")]
+        (0xF, _, 0x7, 0x5) => StoreFlags {
+            up_to_register: b as usize,
+        },
+        #[cfg(feature = "schip")]
+        (0xF, _, 0x8, 0x5) => LoadFlags {
+            up_to_register: b as usize,
+        },
+        _ => return Err(DecodeError::InvalidOpcode(instruction)),
+    };
+
+    Ok(decoded)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Instruction {
     // Routines
     /// 2nnn
@@ -146,11 +186,7 @@ pub enum Instruction {
     /// 1nnn
     Jump { address: u16 },
     /// Bnnn
-    JumpOffset {
-        base_address: u16,
-        #[cfg(feature = "modern")]
-        register: usize,
-    },
+    JumpOffset { base_address: u16, register: usize },
     /// 3xkk
     SkipEqualByte { register: usize, byte: u8 },
     /// 4xkk
@@ -182,6 +218,10 @@ pub enum Instruction {
     SetIndexWithAddress { address: u16 },
     /// Fx29
     SetIndexWithFontAddress { register: usize },
+    /// Fx30 (SUPER-CHIP)
+    SetIndexWithBigFontAddress { register: usize },
+    /// F000 nnnn (XO-CHIP), the address is the word following the opcode
+    SetIndexLong,
 
     // Arithmetic operations
     /// 7xkk
@@ -206,13 +246,11 @@ pub enum Instruction {
     /// 8xy6
     ShiftRight {
         register_x: usize,
-        #[cfg(not(feature = "modern"))]
         register_y: usize,
     },
     /// 8xyE
     ShiftLeft {
         register_x: usize,
-        #[cfg(not(feature = "modern"))]
         register_y: usize,
     },
 
@@ -236,12 +274,24 @@ pub enum Instruction {
     // Display
     /// 00E0
     ClearScreen,
-    /// Dxyn
+    /// Dxyn, Dxy0 draws a 16x16 sprite (SUPER-CHIP)
     Draw {
         register_x: usize,
         register_y: usize,
         n: u8,
     },
+    /// 00Cn (SUPER-CHIP)
+    ScrollDown { n: u8 },
+    /// 00FB (SUPER-CHIP)
+    ScrollRight,
+    /// 00FC (SUPER-CHIP)
+    ScrollLeft,
+    /// 00FE (SUPER-CHIP)
+    LowResolution,
+    /// 00FF (SUPER-CHIP)
+    HighResolution,
+    /// Fn01 (XO-CHIP)
+    SelectPlanes { mask: u8 },
 
     // Timers
     /// Fx07
@@ -250,12 +300,20 @@ pub enum Instruction {
     SetDelayTimer { register: usize },
     /// Fx18
     SetSoundTimer { register: usize },
+    /// Fx3A (XO-CHIP)
+    SetPitch { register: usize },
 
     // RAM load and store
     /// Fx55
     StoreRegisters { up_to_register: usize },
     /// Fx65
     LoadIntoRegisters { up_to_register: usize },
+    /// Fx75 (SUPER-CHIP)
+    StoreFlags { up_to_register: usize },
+    /// Fx85 (SUPER-CHIP)
+    LoadFlags { up_to_register: usize },
+    /// F002 (XO-CHIP), loads the 16 byte audio pattern from I
+    LoadAudioPattern,
 
     // Misc
     /// Fx33
@@ -264,8 +322,109 @@ pub enum Instruction {
     WaitForKey { register: usize },
     /// Cxkk
     RandomAnd { register: usize, byte: u8 },
+    /// 00FD (SUPER-CHIP)
+    Exit,
 
     // Defunct
     /// 0nnn
     MachineRoutine { address: u16 },
 }
+
+/// Standard mnemonics, such as `LD V3, 0x20` or `DRW V1, V2, 5`.
+impl Display for Instruction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Instruction::*;
+
+        match *self {
+            Call { address } => write!(f, "CALL {address:#05X}"),
+            Return => write!(f, "RET"),
+            Jump { address } => write!(f, "JP {address:#05X}"),
+            JumpOffset {
+                base_address,
+                register,
+            } => write!(f, "JP V{register:X}, {base_address:#05X}"),
+            SkipEqualByte { register, byte } => write!(f, "SE V{register:X}, {byte:#04X}"),
+            SkipNotEqualByte { register, byte } => write!(f, "SNE V{register:X}, {byte:#04X}"),
+            SkipEqualVariable {
+                register_x,
+                register_y,
+            } => write!(f, "SE V{register_x:X}, V{register_y:X}"),
+            SkipNotEqualVariable {
+                register_x,
+                register_y,
+            } => write!(f, "SNE V{register_x:X}, V{register_y:X}"),
+            SkipKey { register } => write!(f, "SKP V{register:X}"),
+            SkipNotKey { register } => write!(f, "SKNP V{register:X}"),
+            SetWithByte { register, byte } => write!(f, "LD V{register:X}, {byte:#04X}"),
+            SetWithVariable {
+                register_x,
+                register_y,
+            } => write!(f, "LD V{register_x:X}, V{register_y:X}"),
+            SetIndexWithAddress { address } => write!(f, "LD I, {address:#05X}"),
+            SetIndexWithFontAddress { register } => write!(f, "LD F, V{register:X}"),
+            SetIndexWithBigFontAddress { register } => write!(f, "LD HF, V{register:X}"),
+            // The address is in the following word, which the instruction doesn't hold
+            SetIndexLong => write!(f, "LD I, LONG"),
+            AddWithByte { register, byte } => write!(f, "ADD V{register:X}, {byte:#04X}"),
+            AddWithVariable {
+                register_x,
+                register_y,
+            } => write!(f, "ADD V{register_x:X}, V{register_y:X}"),
+            AddIndexWithVariable { register } => write!(f, "ADD I, V{register:X}"),
+            SubWithVariable {
+                register_x,
+                register_y,
+            } => write!(f, "SUB V{register_x:X}, V{register_y:X}"),
+            SubWithVariableNot {
+                register_x,
+                register_y,
+            } => write!(f, "SUBN V{register_x:X}, V{register_y:X}"),
+            ShiftRight {
+                register_x,
+                register_y,
+            } => write!(f, "SHR V{register_x:X}, V{register_y:X}"),
+            ShiftLeft {
+                register_x,
+                register_y,
+            } => write!(f, "SHL V{register_x:X}, V{register_y:X}"),
+            Or {
+                register_x,
+                register_y,
+            } => write!(f, "OR V{register_x:X}, V{register_y:X}"),
+            And {
+                register_x,
+                register_y,
+            } => write!(f, "AND V{register_x:X}, V{register_y:X}"),
+            Xor {
+                register_x,
+                register_y,
+            } => write!(f, "XOR V{register_x:X}, V{register_y:X}"),
+            ClearScreen => write!(f, "CLS"),
+            Draw {
+                register_x,
+                register_y,
+                n,
+            } => write!(f, "DRW V{register_x:X}, V{register_y:X}, {n}"),
+            ScrollDown { n } => write!(f, "SCD {n}"),
+            ScrollRight => write!(f, "SCR"),
+            ScrollLeft => write!(f, "SCL"),
+            LowResolution => write!(f, "LOW"),
+            HighResolution => write!(f, "HIGH"),
+            SelectPlanes { mask } => write!(f, "PLANE {mask}"),
+            SetVariableWithDelayTimer { register } => write!(f, "LD V{register:X}, DT"),
+            SetDelayTimer { register } => write!(f, "LD DT, V{register:X}"),
+            SetSoundTimer { register } => write!(f, "LD ST, V{register:X}"),
+            SetPitch { register } => write!(f, "PITCH V{register:X}"),
+            StoreRegisters { up_to_register } => write!(f, "LD [I], V{up_to_register:X}"),
+            LoadIntoRegisters { up_to_register } => write!(f, "LD V{up_to_register:X}, [I]"),
+            StoreFlags { up_to_register } => write!(f, "LD R, V{up_to_register:X}"),
+            LoadFlags { up_to_register } => write!(f, "LD V{up_to_register:X}, R"),
+            LoadAudioPattern => write!(f, "AUDIO"),
+            StoreDecimalConversion { register } => write!(f, "LD B, V{register:X}"),
+            WaitForKey { register } => write!(f, "LD V{register:X}, K"),
+            RandomAnd { register, byte } => write!(f, "RND V{register:X}, {byte:#04X}"),
+            Exit => write!(f, "EXIT"),
+            MachineRoutine { address } => write!(f, "SYS {address:#05X}"),
+        }
+    }
+}
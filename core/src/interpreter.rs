@@ -1,11 +1,28 @@
-use rand::{rngs::OsRng, Rng};
+use alloc::{boxed::Box, collections::VecDeque, vec, vec::Vec};
+use core::{
+    fmt::{Debug, Display},
+    time::Duration,
+};
+
+use rand::{rngs::SmallRng, Rng, RngCore, SeedableRng};
 
+#[cfg(feature = "debug")]
+use crate::debug::Debugger;
 use crate::{
-    core::{Ram, Screen, Stack, Timer, VariableRegisters},
+    audio::{pattern_bit, AudioOutput, AudioSink},
+    core::{
+        Banks, Ram, Resolution, Screen, Segment, Stack, Timer, VariableRegisters, BIG_FONT_ADDRESS,
+        BIG_FONT_DATA, FONT_DATA,
+    },
+    error::{InterpreterError, LoadError, SaveStateError},
+    flags::{FlagStorage, MemoryFlags},
+    hash::StableHasher,
     instructions::{decode, Instruction},
+    savestate::{SaveState, Snapshot},
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Key {
     Key0,
     Key1,
@@ -72,7 +89,8 @@ impl From<Key> for u8 {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyState {
     // Key is not pressed (including if it was just released)
     NotPressed,
@@ -82,12 +100,367 @@ pub enum KeyState {
     AlreadyPressed,
 }
 
+/// Keypad state, updated by the frontend through `press` and `release`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InputHandler {
-    pub keys_state: [KeyState; 16],
-    pub waiting: Option<usize>,
-    pub pressed_and_released: Option<Key>,
+    pub(crate) keys_state: [KeyState; 16],
+    // Register Fx0A stores the key in, while waiting
+    pub(crate) waiting: Option<usize>,
+    // Key pressed and released since the wait started, which resolves it
+    pub(crate) pressed_and_released: Option<Key>,
+}
+
+impl InputHandler {
+    pub(crate) fn new() -> Self {
+        Self {
+            keys_state: [KeyState::NotPressed; 16],
+            waiting: None,
+            pressed_and_released: None,
+        }
+    }
+
+    pub fn press(&mut self, key: Key) {
+        let state = &mut self.keys_state[u8::from(key) as usize];
+        if let KeyState::NotPressed = state {
+            *state = KeyState::Pressed;
+        }
+    }
+
+    pub fn release(&mut self, key: Key) {
+        let state = &mut self.keys_state[u8::from(key) as usize];
+        match state {
+            KeyState::Pressed => {
+                *state = KeyState::NotPressed;
+                // A full press and release resolves a pending Fx0A
+                if self.waiting.is_some() {
+                    self.pressed_and_released = Some(key);
+                }
+            }
+            KeyState::AlreadyPressed => *state = KeyState::NotPressed,
+            KeyState::NotPressed => {}
+        }
+    }
+
+    /// Presses or releases `key`, convenient for frontends polling the whole keypad.
+    pub fn set(&mut self, key: Key, pressed: bool) {
+        match pressed {
+            true => self.press(key),
+            false => self.release(key),
+        }
+    }
+
+    pub fn is_pressed(&self, key: Key) -> bool {
+        !matches!(
+            self.keys_state[u8::from(key) as usize],
+            KeyState::NotPressed
+        )
+    }
+
+    /// Register Fx0A will store the next key in, if it is waiting for one.
+    pub fn waiting(&self) -> Option<usize> {
+        self.waiting
+    }
+
+    pub(crate) fn start_waiting(&mut self, register: usize) {
+        // Keys held down before the wait started don't count
+        self.keys_state = self.keys_state.map(|state| match state {
+            KeyState::NotPressed => state,
+            KeyState::Pressed => KeyState::AlreadyPressed,
+            KeyState::AlreadyPressed => state,
+        });
+        self.pressed_and_released = None;
+        self.waiting = Some(register);
+    }
+}
+
+// Square wave heard until a program loads its own pattern with F002
+const DEFAULT_AUDIO_PATTERN: [u8; 16] = [0xF0; 16];
+
+// 2^(1/48), the playback rate ratio between two pitch values
+const PITCH_STEP: f32 = 1.014_545_3;
+
+// Two-page hi-res ROMs start by jumping to their 1802 code at 0x260, which switches
+// the VIP to 64x64. That code can't run here, so the mode is set up directly and
+// the program starts past it.
+const TWO_PAGE_ENTRY: [u8; 2] = [0x12, 0x60];
+const TWO_PAGE_START: u16 = 0x2C0;
+// Clears the screen in the two-page mode
+const TWO_PAGE_CLEAR: u16 = 0x230;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemoryProtection {
+    // Writes go through, some programs deliberately modify the font
+    Allow,
+    // Writes are silently dropped
+    Ignore,
+    // Writes stop execution with an error before modifying memory
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfModifyingCode {
+    // Writes to executed code are allowed silently
+    Ignore,
+    // Writes are allowed and recorded, see `take_self_modifying_writes`
+    Report,
+    // Writes stop execution with an error before modifying memory
+    Trap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfModifyingWrite {
+    // Address of the instruction performing the write
+    pub pc: u16,
+    // Written address, which previously held executed code
+    pub address: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AudioEventKind {
+    BeepStart,
+    BeepStop,
+    // F002 loaded a different XO-CHIP audio pattern
+    PatternChanged { pattern: [u8; 16] },
+    // Fx3A set a different XO-CHIP pitch
+    PitchChanged { pitch: u8 },
+}
+
+/// A change in sound output, stamped with when it happened in emulated time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioEvent {
+    pub kind: AudioEventKind,
+    // Instructions executed before the event
+    pub cycle: u64,
+    // Timer ticks before the event
+    pub frame: u64,
+}
+
+/// A key change queued with [`Interpreter::queue_key_event`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyEvent {
+    pub key: Key,
+    pub pressed: bool,
+    // Frame the change applies at, events for past frames apply at the next one
+    pub frame: u64,
+}
+
+/// A queued key event along with the frame it was applied at, recorded when
+/// `Interpreter::record_key_latency` is set. Events come out in the order they
+/// were applied, by frame and then in the order they were queued.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppliedKeyEvent {
+    pub event: KeyEvent,
+    pub applied_at: u64,
+}
+
+impl AppliedKeyEvent {
+    /// Frames the event waited in the queue past the frame it was queued for.
+    pub fn latency(&self) -> u64 {
+        self.applied_at.saturating_sub(self.event.frame)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Status {
+    Running,
+    // Blocked on Fx0A, the key will be stored in the register
+    WaitingForKey { register: usize },
+    Paused,
+    // The program exited with 00FD
+    Halted,
+    // The program jumped to itself, nothing can change anymore
+    Finished,
+    // Stepping keeps returning this error
+    Errored(InterpreterError),
+    // Stopped before the instruction at this address, the next step runs it. Only
+    // reported with the `debug` feature
+    BreakpointHit(u16),
+}
+
+/// What to do with an instruction, decided by a hook before it runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookAction {
+    Continue,
+    // The instruction is dropped, but still counts as a cycle. The program counter
+    // moves past it, including the operand word of F000 nnnn
+    Skip,
+    // Execution stops with `InterpreterError::Aborted`
+    Abort,
+}
+
+/// What to do with an opcode the decoder doesn't know, decided by a handler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IllegalInstructionAction {
+    // Carry on with the next instruction, after any emulation the handler did
+    Skip,
+    // Stop the program as if it executed 00FD
+    Halt,
+    // Fail with the decode error, as without a handler
+    Error,
 }
 
+type Hook = Box<dyn FnMut(&Interpreter, &Instruction) -> HookAction + Send>;
+type PostHook = Box<dyn FnMut(&Interpreter, &Instruction) + Send>;
+type IllegalInstructionHandler =
+    Box<dyn FnMut(&mut Interpreter, u16) -> IllegalInstructionAction + Send>;
+type MachineRoutineHandler =
+    Box<dyn FnMut(&mut Interpreter, u16) -> Result<(), InterpreterError> + Send>;
+
+/// What a call to `step` or `run_frame` did, so frontends only redraw or poll what changed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StepOutcome {
+    /// Instructions executed, 0 while paused, halted, finished or waiting for a key.
+    pub cycles: u64,
+    /// The instruction drew, cleared, scrolled or switched resolution.
+    pub screen_changed: bool,
+    /// The sound timer went from zero to running.
+    pub sound_started: bool,
+    /// The sound timer ran out, or was set to zero.
+    pub sound_stopped: bool,
+    /// Execution is blocked on Fx0A until a key is pressed.
+    pub waiting_for_key: bool,
+    /// Frames fast-forwarded by idle-loop skipping. Both timers have already
+    /// been ticked for them, the frontend should wait that many frames
+    /// without stepping to keep the game at its normal pace.
+    pub skipped_frames: u8,
+}
+
+/// How fast the machine runs, used by `run_for`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClockConfig {
+    /// Instructions executed per second of emulated time.
+    pub instructions_per_second: u32,
+    /// Timer ticks per second, 60 on the original machines. Audio event frame
+    /// stamps count these ticks. 0 is treated as 1.
+    pub timer_hz: u32,
+}
+
+impl ClockConfig {
+    /// Runs `instructions_per_second` instructions per second with 60 Hz timers.
+    pub fn per_second(instructions_per_second: u32) -> Self {
+        Self {
+            instructions_per_second,
+            timer_hz: 60,
+        }
+    }
+
+    /// Runs `instructions_per_frame` instructions between each 60 Hz timer tick.
+    /// Very large values give "ludicrous speed" for games that pace themselves on
+    /// the delay timer, the rate saturates at `u32::MAX` instructions per second.
+    pub fn per_frame(instructions_per_frame: u32) -> Self {
+        Self::per_second(instructions_per_frame.saturating_mul(60))
+    }
+
+    /// Time between two timer ticks, at least a nanosecond.
+    pub fn frame_duration(&self) -> Duration {
+        (Duration::from_secs(1) / self.timer_rate()).max(Duration::from_nanos(1))
+    }
+
+    // `timer_hz` is public, so a zero rate is only caught where it's used
+    pub(crate) fn timer_rate(&self) -> u32 {
+        self.timer_hz.max(1)
+    }
+
+    // Instructions to run in the given frame, spreading rates that don't divide
+    // evenly into the timer rate over consecutive frames
+    fn instructions_in_frame(&self, frame: u64) -> usize {
+        let (rate, hz) = (
+            self.instructions_per_second as u64,
+            self.timer_rate() as u64,
+        );
+        ((frame + 1) * rate / hz - frame * rate / hz) as usize
+    }
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self::per_frame(11)
+    }
+}
+
+/// Processor state restored along with a memory image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuState {
+    pub program_counter: u16,
+    pub index_register: u16,
+    pub variable_registers: [u8; 16],
+    // Return addresses, from the bottom of the stack to the top
+    pub stack: Vec<u16>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyWait {
+    // Fx0A resolves as soon as a key is pressed
+    Press,
+    // Fx0A resolves once a key is pressed and released
+    Release,
+    // Like `Release`, with a tone while the key is held, as on the COSMAC VIP
+    ReleaseWithTone,
+}
+
+/// Behaviors that differ between the original COSMAC VIP interpreter and later ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quirks {
+    // 8xy6/8xyE copy Vy into Vx before shifting
+    pub shift_reads_vy: bool,
+    // Fx55/Fx65 leave I pointing past the last register
+    pub increment_index: bool,
+    // Bnnn jumps to nnn + Vx instead of nnn + V0
+    pub jump_uses_vx: bool,
+    // Sprites wrap around the screen edges instead of being clipped
+    pub wrap_sprites: bool,
+    // 8xy1/8xy2/8xy3 reset VF to 0
+    pub vf_reset: bool,
+    pub key_wait: KeyWait,
+    // Memory accesses past the end of RAM wrap around to 0 instead of failing
+    pub wrap_memory: bool,
+}
+
+impl Quirks {
+    pub const COSMAC: Self = Self {
+        shift_reads_vy: true,
+        increment_index: true,
+        jump_uses_vx: false,
+        wrap_sprites: false,
+        vf_reset: true,
+        key_wait: KeyWait::Release,
+        wrap_memory: false,
+    };
+
+    pub const MODERN: Self = Self {
+        shift_reads_vy: false,
+        increment_index: false,
+        jump_uses_vx: true,
+        wrap_sprites: false,
+        vf_reset: false,
+        key_wait: KeyWait::Release,
+        wrap_memory: false,
+    };
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::COSMAC
+    }
+}
+
+// Callbacks, the audio sink and the flag storage aren't serialized, and the random
+// generator is reseeded on deserialization
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Interpreter {
     pub ram: Ram,
     pub screen: Screen,
@@ -98,143 +471,1357 @@ pub struct Interpreter {
     pub delay_timer: Timer,
     pub sound_timer: Timer,
     pub input_handler: InputHandler,
+    pub quirks: Quirks,
+    /// What happens to writes into the interpreter-reserved area (0x000-0x1FF)
+    pub memory_protection: MemoryProtection,
+    pub self_modifying_code: SelfModifyingCode,
+    /// Fast-forward timers instead of spinning in delay timer wait loops
+    pub idle_loop_skipping: bool,
+    /// Record beep start/stop and pattern/pitch change events, see `take_audio_events`
+    pub record_audio_events: bool,
+    /// Record the frame each queued key event is applied at, see `take_applied_key_events`
+    pub record_key_latency: bool,
+    /// Calls nested deeper than this fail with a stack overflow, the original allows 16
+    pub max_stack_depth: usize,
+    /// Tick the timers every this many steps, tying them to the instruction count instead of
+    /// wall-clock time. The frontend must not call `tick_timers` itself when this is set.
+    pub instructions_per_tick: Option<u32>,
+    /// Instruction and timer rates followed by `run_for`
+    pub clock: ClockConfig,
+    /// Breakpoints checked before each instruction
+    #[cfg(feature = "debug")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub debugger: Debugger,
+    // Emulated time handed to `run_for` that hasn't been run yet
+    pending_time: Duration,
+    // Frames left to wait out after idle-loop skipping fast-forwarded the timers
+    idle_frames: u8,
+    // Where programs are loaded and started
+    start_address: u16,
+    // Copied into RAM whenever a program is loaded
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_arrays"))]
+    font: [u8; 80],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_arrays"))]
+    big_font: [u8; 160],
+    paused: bool,
+    // Set by 00FD, nothing runs past it
+    halted: bool,
+    // Set by a 1nnn jumping to itself, the usual way programs end
+    finished: bool,
+    error: Option<InterpreterError>,
+    cycles: u64,
+    frames: u64,
+    // Steps taken since the last tick, when ticking by instruction count
+    steps_since_tick: u32,
+    audio_events: Vec<AudioEvent>,
+    // Key changes waiting for their frame, in frame order
+    key_events: VecDeque<KeyEvent>,
+    applied_key_events: Vec<AppliedKeyEvent>,
+    // XO-CHIP 1-bit waveform, played most significant bit first while the sound timer runs
+    audio_pattern: [u8; 16],
+    // XO-CHIP pitch register, 64 plays the pattern at 4000 bits per second
+    pitch: u8,
+    // Position in the pattern, in bits, carried over between sound buffers
+    audio_phase: f32,
+    // One bit per RAM address, set once the address has been fetched as code
+    executed: Vec<u64>,
+    self_modifying_writes: Vec<SelfModifyingWrite>,
+    banks: Option<Banks>,
+    // SUPER-CHIP flag registers (HP-48 RPL user flags)
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_flag_storage"))]
+    flag_storage: Box<dyn FlagStorage + Send>,
+    // Last flags loaded from or saved to the storage, which clones start from
+    #[cfg_attr(feature = "serde", serde(skip))]
+    flags: [u8; 16],
+    // Called before and after each instruction, for tracers and debuggers
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hook: Option<Hook>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    post_hook: Option<PostHook>,
+    // Emulates 0nnn machine code routines, which are ignored without it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    machine_routine_handler: Option<MachineRoutineHandler>,
+    // Decides what happens on unknown opcodes, which are errors without it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    illegal_instruction_handler: Option<IllegalInstructionHandler>,
+    // Fed with the sound output of each frame
+    #[cfg_attr(feature = "serde", serde(skip))]
+    audio_output: Option<AudioOutput>,
+    // Source of Cxkk random bytes
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_rng"))]
+    rng: Box<dyn CloneRng>,
+}
+
+/// Clones the machine along with its random generator state and flag registers, so
+/// the clone runs exactly like the original.
+///
+/// Callbacks and sinks are not carried over: the clone has no hooks, no machine
+/// routine or illegal instruction handler and no audio sink. Its flag registers
+/// start as a copy of the current ones, kept in memory rather than in the
+/// original's storage.
+impl Clone for Interpreter {
+    fn clone(&self) -> Self {
+        Self {
+            ram: self.ram.clone(),
+            screen: self.screen.clone(),
+            variable_registers: self.variable_registers.clone(),
+            index_register: self.index_register,
+            program_counter: self.program_counter,
+            stack: self.stack.clone(),
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            input_handler: self.input_handler.clone(),
+            quirks: self.quirks,
+            memory_protection: self.memory_protection,
+            self_modifying_code: self.self_modifying_code,
+            idle_loop_skipping: self.idle_loop_skipping,
+            record_audio_events: self.record_audio_events,
+            record_key_latency: self.record_key_latency,
+            max_stack_depth: self.max_stack_depth,
+            instructions_per_tick: self.instructions_per_tick,
+            clock: self.clock,
+            #[cfg(feature = "debug")]
+            debugger: self.debugger.clone(),
+            pending_time: self.pending_time,
+            idle_frames: self.idle_frames,
+            start_address: self.start_address,
+            font: self.font,
+            big_font: self.big_font,
+            paused: self.paused,
+            halted: self.halted,
+            finished: self.finished,
+            error: self.error.clone(),
+            cycles: self.cycles,
+            frames: self.frames,
+            steps_since_tick: self.steps_since_tick,
+            audio_events: self.audio_events.clone(),
+            key_events: self.key_events.clone(),
+            applied_key_events: self.applied_key_events.clone(),
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            audio_phase: self.audio_phase,
+            executed: self.executed.clone(),
+            self_modifying_writes: self.self_modifying_writes.clone(),
+            banks: self.banks.clone(),
+            flag_storage: Box::new(MemoryFlags(self.flags)),
+            flags: self.flags,
+            hook: None,
+            post_hook: None,
+            machine_routine_handler: None,
+            illegal_instruction_handler: None,
+            audio_output: None,
+            rng: self.rng.clone_box(),
+        }
+    }
+}
+
+/// Compares the emulated machine: memory, registers, timers, keys, the flag registers
+/// and whether it stopped or failed.
+///
+/// Configuration such as the quirks, clock and memory protection is left out, so
+/// two machines in the same state compare equal even when set up to behave
+/// differently from there on. Callbacks and the random generator can't be compared.
+impl PartialEq for Interpreter {
+    fn eq(&self, other: &Self) -> bool {
+        self.ram == other.ram
+            && self.screen == other.screen
+            && self.variable_registers == other.variable_registers
+            && self.index_register == other.index_register
+            && self.program_counter == other.program_counter
+            && self.stack == other.stack
+            && self.delay_timer == other.delay_timer
+            && self.sound_timer == other.sound_timer
+            && self.input_handler == other.input_handler
+            && self.paused == other.paused
+            && self.halted == other.halted
+            && self.finished == other.finished
+            && self.error == other.error
+            && self.cycles == other.cycles
+            && self.frames == other.frames
+            && self.key_events == other.key_events
+            && self.audio_pattern == other.audio_pattern
+            && self.pitch == other.pitch
+            && self.banks == other.banks
+            && self.flags == other.flags
+    }
+}
+
+impl Debug for Interpreter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Interpreter")
+            .field("status", &self.status())
+            .field("program_counter", &self.program_counter)
+            .field("index_register", &self.index_register)
+            .field("variable_registers", &self.variable_registers)
+            .field("stack", &self.stack)
+            .field("delay_timer", &self.delay_timer)
+            .field("sound_timer", &self.sound_timer)
+            .field("input_handler", &self.input_handler)
+            .field("quirks", &self.quirks)
+            .field("cycles", &self.cycles)
+            .field("frames", &self.frames)
+            .field("ram", &self.ram)
+            .field("screen", &self.screen)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Interpreter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Program counter along with the instruction it points to
+        let pc = self.program_counter as usize;
+        write!(f, "PC: {:#05X}", self.program_counter)?;
+        if let Some(&[high, low]) = self.ram.get(pc..pc + 2) {
+            let instruction = u16::from_be_bytes([high, low]);
+            match decode(instruction) {
+                Ok(decoded_instruction) => write!(f, " ({instruction:04X} {decoded_instruction})")?,
+                Err(_) => write!(f, " ({instruction:04X} invalid)")?,
+            }
+        }
+        writeln!(f)?;
+
+        writeln!(f, "I:  {:#05X}", self.index_register)?;
+        for first_register in [0x0, 0x8] {
+            write!(f, "V{:X}-V{:X}:", first_register, first_register + 7)?;
+            for register in first_register..first_register + 8 {
+                write!(f, " {:02X}", self.variable_registers[register])?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "DT: {:02X}  ST: {:02X}",
+            self.delay_timer.value, self.sound_timer.value
+        )?;
+
+        writeln!(f, "Stack: {}", self.stack)?;
+
+        write!(f, "{}", self.screen.compact())
+    }
+}
+
+// A random generator that can be cloned along with the interpreter
+pub(crate) trait CloneRng: RngCore + Send {
+    fn clone_box(&self) -> Box<dyn CloneRng>;
+}
+
+impl<R: RngCore + Clone + Send + 'static> CloneRng for R {
+    fn clone_box(&self) -> Box<dyn CloneRng> {
+        Box::new(self.clone())
+    }
+}
+
+fn default_flag_storage() -> Box<dyn FlagStorage + Send> {
+    Box::new(MemoryFlags::default())
+}
+
+// Seeded from the OS when there is one, from a fixed seed otherwise
+fn default_rng() -> Box<dyn CloneRng> {
+    #[cfg(feature = "std")]
+    let rng = SmallRng::from_entropy();
+    #[cfg(not(feature = "std"))]
+    let rng = SmallRng::seed_from_u64(0xC8);
+
+    Box::new(rng)
+}
+
+/// Configures an interpreter before loading its program.
+pub struct InterpreterBuilder {
+    memory_size: usize,
+    start_address: u16,
+    font: [u8; 80],
+    big_font: [u8; 160],
+    rng: Option<Box<dyn CloneRng>>,
+    instructions_per_tick: Option<u32>,
+}
+
+impl Default for InterpreterBuilder {
+    fn default() -> Self {
+        Self {
+            memory_size: 4096,
+            start_address: 0x200,
+            font: FONT_DATA,
+            big_font: BIG_FONT_DATA,
+            rng: None,
+            instructions_per_tick: None,
+        }
+    }
+}
+
+impl InterpreterBuilder {
+    /// RAM size in bytes, from just over 512 bytes up to 64 KiB. The default is
+    /// 4 KiB, the original 2 KiB VIP had 2048 and XO-CHIP programs expect 64 KiB.
+    pub fn memory_size(mut self, memory_size: usize) -> Self {
+        self.memory_size = memory_size;
+        self
+    }
+
+    /// Address the program is loaded at and started from, 0x200 by default.
+    /// ETI-660 programs start at 0x600.
+    pub fn start_address(mut self, start_address: u16) -> Self {
+        self.start_address = start_address;
+        self
+    }
+
+    /// Replaces the 4x5 hexadecimal font, five bytes per digit from 0 to F.
+    pub fn font(mut self, font: &[u8; 80]) -> Self {
+        self.font = *font;
+        self
+    }
+
+    /// Replaces the SUPER-CHIP 8x10 font, ten bytes per digit from 0 to F.
+    pub fn big_font(mut self, big_font: &[u8; 160]) -> Self {
+        self.big_font = *big_font;
+        self
+    }
+
+    /// Random source for Cxkk, a generator seeded from the OS by default.
+    pub fn rng(mut self, rng: impl RngCore + Clone + Send + 'static) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Makes runs reproducible: the same program and inputs always give the same state.
+    /// Cxkk draws from a generator seeded with `seed`, and the timers tick every
+    /// `instructions_per_frame` steps instead of following wall-clock time.
+    pub fn deterministic(mut self, seed: u64, instructions_per_frame: u32) -> Self {
+        self.rng = Some(Box::new(SmallRng::seed_from_u64(seed)));
+        self.instructions_per_tick = Some(instructions_per_frame);
+        self
+    }
+
+    pub fn build(self, program: &[u8]) -> Result<Interpreter, LoadError> {
+        let mut ram = Ram::new(self.memory_size, &self.font, &self.big_font)?;
+        ram.load_program(program, self.start_address)?;
+
+        let mut interpreter = Interpreter::with_ram(ram, self.start_address);
+        interpreter.font = self.font;
+        interpreter.big_font = self.big_font;
+        if let Some(rng) = self.rng {
+            interpreter.rng = rng;
+        }
+        interpreter.instructions_per_tick = self.instructions_per_tick;
+        interpreter.detect_two_page_mode();
+        Ok(interpreter)
+    }
 }
 
 impl Interpreter {
-    pub fn new(program: &[u8]) -> Self {
-        let mut ram = Ram::new();
-        ram.load_program(program);
+    pub fn new(program: &[u8]) -> Result<Self, LoadError> {
+        Self::builder().build(program)
+    }
+
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    fn with_ram(ram: Ram, start_address: u16) -> Self {
+        let executed = vec![0; ram.len().div_ceil(64)];
 
         Self {
             ram,
             variable_registers: VariableRegisters::new(),
             index_register: 0,
-            program_counter: 0x200,
+            program_counter: start_address,
             stack: Stack::new(),
             screen: Screen::new(),
             delay_timer: Timer::new(),
             sound_timer: Timer::new(),
-            input_handler: InputHandler {
-                keys_state: [KeyState::NotPressed; 16],
-                waiting: None,
-                pressed_and_released: None,
-            },
+            input_handler: InputHandler::new(),
+            quirks: Quirks::default(),
+            memory_protection: MemoryProtection::Allow,
+            self_modifying_code: SelfModifyingCode::Ignore,
+            idle_loop_skipping: false,
+            record_audio_events: false,
+            record_key_latency: false,
+            max_stack_depth: 16,
+            instructions_per_tick: None,
+            clock: ClockConfig::default(),
+            #[cfg(feature = "debug")]
+            debugger: Debugger::default(),
+            pending_time: Duration::ZERO,
+            idle_frames: 0,
+            start_address,
+            font: FONT_DATA,
+            big_font: BIG_FONT_DATA,
+            paused: false,
+            halted: false,
+            finished: false,
+            error: None,
+            cycles: 0,
+            frames: 0,
+            steps_since_tick: 0,
+            audio_events: Vec::new(),
+            key_events: VecDeque::new(),
+            applied_key_events: Vec::new(),
+            audio_pattern: DEFAULT_AUDIO_PATTERN,
+            pitch: 64,
+            audio_phase: 0.0,
+            executed,
+            self_modifying_writes: Vec::new(),
+            banks: None,
+            flag_storage: default_flag_storage(),
+            flags: [0; 16],
+            hook: None,
+            post_hook: None,
+            machine_routine_handler: None,
+            illegal_instruction_handler: None,
+            audio_output: None,
+            rng: default_rng(),
+        }
+    }
+
+    /// Starts from a full memory image instead of a program at 0x200. The image
+    /// replaces the font data too, and its length sets the RAM size.
+    pub fn from_memory_image(memory: &[u8], cpu: CpuState) -> Result<Self, LoadError> {
+        let mut interpreter = Self::with_ram(Ram::from_image(memory)?, 0x200);
+
+        interpreter.program_counter = cpu.program_counter;
+        interpreter.index_register = cpu.index_register;
+        for (register, value) in cpu.variable_registers.into_iter().enumerate() {
+            interpreter.variable_registers[register] = value;
+        }
+        interpreter.stack = Stack::from_addresses(cpu.stack);
+        interpreter.delay_timer.value = cpu.delay_timer;
+        interpreter.sound_timer.value = cpu.sound_timer;
+
+        Ok(interpreter)
+    }
+
+    /// Resets the machine and swaps in another program, keeping the interpreter
+    /// configuration. On error, the current program is left untouched.
+    pub fn load_new_rom(&mut self, program: &[u8]) -> Result<(), LoadError> {
+        // Checked up front so that memory is only cleared once the program is known to fit
+        self.ram.check_program(program, self.start_address)?;
+        self.ram.reset(&self.font, &self.big_font);
+        self.ram.load_program(program, self.start_address)?;
+
+        self.variable_registers = VariableRegisters::new();
+        self.index_register = 0;
+        self.program_counter = self.start_address;
+        self.stack.clear();
+        self.screen = Screen::new();
+        self.detect_two_page_mode();
+        self.delay_timer = Timer::new();
+        self.sound_timer = Timer::new();
+        self.input_handler = InputHandler::new();
+        self.cycles = 0;
+        self.frames = 0;
+        self.steps_since_tick = 0;
+        self.pending_time = Duration::ZERO;
+        self.idle_frames = 0;
+        self.halted = false;
+        self.finished = false;
+        self.error = None;
+        #[cfg(feature = "debug")]
+        {
+            self.debugger.reset();
+            self.debugger.clear_history();
+        }
+        self.audio_events.clear();
+        self.key_events.clear();
+        self.applied_key_events.clear();
+        self.audio_pattern = DEFAULT_AUDIO_PATTERN;
+        self.pitch = 64;
+        self.audio_phase = 0.0;
+        self.executed.fill(0);
+        self.self_modifying_writes.clear();
+        // Banks hold data belonging to the previous program
+        self.banks = None;
+
+        Ok(())
+    }
+
+    // Programs loaded at 0x200 that start with 1260 are two-page hi-res ROMs
+    fn detect_two_page_mode(&mut self) {
+        let entry = self.start_address as usize;
+        if self.start_address == 0x200 && self.ram.get(entry..entry + 2) == Some(&TWO_PAGE_ENTRY) {
+            self.screen.set_resolution(Resolution::TwoPage);
+            self.program_counter = TWO_PAGE_START;
+        }
+    }
+
+    fn is_two_page_clear(&self, instruction: &Instruction) -> bool {
+        *instruction
+            == Instruction::MachineRoutine {
+                address: TWO_PAGE_CLEAR,
+            }
+            && self.screen.resolution() == Resolution::TwoPage
+    }
+
+    /// Loads additional data blocks, e.g. assets kept apart from the program.
+    pub fn load_segments(&mut self, segments: &[Segment]) -> Result<(), LoadError> {
+        for segment in segments {
+            self.ram.load_segment(segment.address, segment.data)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces where Fx75/Fx85 keep the flag registers, in memory by default.
+    /// The storage is kept when loading a new ROM.
+    pub fn set_flag_storage(&mut self, storage: impl FlagStorage + Send + 'static) {
+        self.flag_storage = Box::new(storage);
+        self.flags = self.flag_storage.load();
+    }
+
+    /// Replaces the generator Cxkk draws from, e.g. with a seeded one for reproducible runs.
+    /// The generator is kept when loading a new ROM.
+    pub fn set_rng(&mut self, rng: impl RngCore + Clone + Send + 'static) {
+        self.rng = Box::new(rng);
+    }
+
+    /// Calls `hook` before each instruction runs, with the program counter already
+    /// past it. The hook decides whether the instruction runs, is skipped, or aborts
+    /// execution. Hooks are kept when loading a new ROM.
+    pub fn set_hook(
+        &mut self,
+        hook: impl FnMut(&Interpreter, &Instruction) -> HookAction + Send + 'static,
+    ) {
+        self.hook = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` after each instruction has run.
+    pub fn set_post_hook(&mut self, hook: impl FnMut(&Interpreter, &Instruction) + Send + 'static) {
+        self.post_hook = Some(Box::new(hook));
+    }
+
+    pub fn clear_hooks(&mut self) {
+        self.hook = None;
+        self.post_hook = None;
+    }
+
+    /// Calls `handler` with the address of each 0nnn machine code routine, instead of
+    /// ignoring them. The handler can emulate the routine by changing the interpreter
+    /// state, or trap it by returning an error. It is kept when loading a new ROM.
+    pub fn set_machine_routine_handler(
+        &mut self,
+        handler: impl FnMut(&mut Interpreter, u16) -> Result<(), InterpreterError> + Send + 'static,
+    ) {
+        self.machine_routine_handler = Some(Box::new(handler));
+    }
+
+    /// Calls `handler` with opcodes the decoder doesn't know, instead of failing. The
+    /// handler can emulate nonstandard extensions by changing the interpreter state.
+    /// It is kept when loading a new ROM.
+    pub fn set_illegal_instruction_handler(
+        &mut self,
+        handler: impl FnMut(&mut Interpreter, u16) -> IllegalInstructionAction + Send + 'static,
+    ) {
+        self.illegal_instruction_handler = Some(Box::new(handler));
+    }
+
+    /// Drives `sink` from [`Interpreter::run_frame`]: beeps are started and stopped
+    /// with the sound timer, and each frame's samples are queued at `sample_rate`.
+    /// It is kept when loading a new ROM.
+    pub fn set_audio_sink(&mut self, sink: impl AudioSink + Send + 'static, sample_rate: u32) {
+        self.audio_output = Some(AudioOutput::new(Box::new(sink), sample_rate));
+    }
+
+    pub fn clear_audio_sink(&mut self) {
+        self.audio_output = None;
+    }
+
+    /// Encodes the machine state into a versioned, checksummed save state, restored
+    /// with [`Interpreter::load_state`].
+    pub fn save_state(&self) -> Vec<u8> {
+        SaveState {
+            ram: self.ram.clone(),
+            screen: self.screen.clone(),
+            variable_registers: core::array::from_fn(|register| self.variable_registers[register]),
+            index_register: self.index_register,
+            program_counter: self.program_counter,
+            stack: self.stack.iter().collect(),
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            cycles: self.cycles,
+            frames: self.frames,
+            steps_since_tick: self.steps_since_tick,
+            paused: self.paused,
+            halted: self.halted,
+            finished: self.finished,
+            key_wait: self.input_handler.waiting,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            banks: self.banks.clone(),
+        }
+        .encode()
+    }
+
+    /// Rewrites a save state from any supported format version in the current one, so
+    /// it keeps loading once support for its version is dropped.
+    pub fn migrate_state(data: &[u8]) -> Result<Vec<u8>, SaveStateError> {
+        Ok(SaveState::decode(data)?.encode())
+    }
+
+    /// Restores a save state made by [`Interpreter::save_state`], in any format version
+    /// still supported (see [`Interpreter::migrate_state`]). Configuration and callbacks
+    /// are kept and held keys are released. On error, the interpreter is left untouched.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+        let state = SaveState::decode(data)?;
+
+        self.executed = vec![0; state.ram.len().div_ceil(64)];
+        self.ram = state.ram;
+        self.screen = state.screen;
+        for (register, value) in state.variable_registers.into_iter().enumerate() {
+            self.variable_registers[register] = value;
+        }
+        self.index_register = state.index_register;
+        self.program_counter = state.program_counter;
+        self.stack = Stack::from_addresses(state.stack);
+        self.delay_timer = state.delay_timer;
+        self.sound_timer = state.sound_timer;
+        self.input_handler = InputHandler::new();
+        self.input_handler.waiting = state.key_wait;
+        self.cycles = state.cycles;
+        self.frames = state.frames;
+        self.steps_since_tick = state.steps_since_tick;
+        self.paused = state.paused;
+        self.halted = state.halted;
+        self.finished = state.finished;
+        self.error = None;
+        #[cfg(feature = "debug")]
+        self.debugger.reset();
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.audio_phase = 0.0;
+        self.banks = state.banks;
+        self.pending_time = Duration::ZERO;
+        self.idle_frames = 0;
+        self.audio_events.clear();
+        self.key_events.clear();
+        self.applied_key_events.clear();
+        self.self_modifying_writes.clear();
+
+        Ok(())
+    }
+
+    // Save state along with the random generator and held keys, which undo restores too
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            state: self.save_state(),
+            rng: self.rng.clone_box(),
+            input_handler: self.input_handler.clone(),
+        }
+    }
+
+    pub(crate) fn restore_snapshot(&mut self, snapshot: &Snapshot) -> bool {
+        if self.load_state(&snapshot.state).is_err() {
+            return false;
+        }
+        self.rng = snapshot.rng.clone_box();
+        self.input_handler = snapshot.input_handler.clone();
+        true
+    }
+
+    /// Hash of the machine state: registers, stack, timers, memory and screen. Stable
+    /// across platforms, so it can be stored in golden tests.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        hasher.write(&self.program_counter.to_le_bytes());
+        hasher.write(&self.index_register.to_le_bytes());
+        for register in 0..16 {
+            hasher.write(&[self.variable_registers[register]]);
         }
+        hasher.write(&[self.stack.depth() as u8]);
+        for address in self.stack.iter() {
+            hasher.write(&address.to_le_bytes());
+        }
+        hasher.write(&[self.delay_timer.value, self.sound_timer.value]);
+        hasher.write(self.ram.get(0..self.ram.len()).unwrap_or_default());
+        self.screen.write_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Installs memory banks and maps the first one into its RAM window.
+    pub fn set_banks(&mut self, banks: Banks) -> Result<(), LoadError> {
+        banks.load(&mut self.ram)?;
+        self.banks = Some(banks);
+        Ok(())
+    }
+
+    pub fn banks(&self) -> Option<&Banks> {
+        self.banks.as_ref()
+    }
+
+    pub fn switch_bank(&mut self, bank: usize) -> Result<(), LoadError> {
+        match &mut self.banks {
+            Some(banks) => banks.switch(&mut self.ram, bank),
+            None => Err(LoadError::InvalidBank { bank }),
+        }
+    }
+
+    /// Number of instructions executed so far.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Number of timer ticks so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Freezes execution and timers until `resume` is called.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        if !self.paused {
+            return;
+        }
+        self.paused = false;
+
+        // Restart a pending Fx0A wait, so that keys pressed while paused are ignored
+        if let Some(register) = self.input_handler.waiting {
+            self.input_handler.start_waiting(register);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Address programs are loaded at and started from.
+    pub fn start_address(&self) -> u16 {
+        self.start_address
+    }
+
+    /// Whether the program exited with 00FD.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Whether the program is stuck on a jump to itself, which ROMs often end with.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Decrements both timers, to be called at 60 Hz.
+    pub fn tick_timers(&mut self) {
+        if self.paused {
+            return;
+        }
+
+        let sounding = self.sound_timer.value > 0;
+
+        self.delay_timer.decrement();
+        self.sound_timer.decrement();
+        self.frames += 1;
+
+        if sounding && self.sound_timer.value == 0 {
+            self.push_audio_event(AudioEventKind::BeepStop);
+        }
+    }
+
+    /// Returns the key events applied since the last call, with the frame each one
+    /// reached the program at.
+    pub fn take_applied_key_events(&mut self) -> Vec<AppliedKeyEvent> {
+        core::mem::take(&mut self.applied_key_events)
+    }
+
+    /// Returns the audio events recorded since the last call.
+    pub fn take_audio_events(&mut self) -> Vec<AudioEvent> {
+        core::mem::take(&mut self.audio_events)
+    }
+
+    pub fn audio_pattern(&self) -> &[u8; 16] {
+        &self.audio_pattern
+    }
+
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Rate at which the audio pattern bits are played, in bits per second.
+    pub fn playback_rate(&self) -> f32 {
+        // 4000 * 2^((pitch - 64) / 48), without relying on std for powf
+        let steps = self.pitch as i32 - 64;
+        let octaves = steps.div_euclid(48);
+        let octave_factor = if octaves < 0 { 0.5 } else { 2.0 };
+        let mut rate = 4000.0;
+        for _ in 0..octaves.abs() {
+            rate *= octave_factor;
+        }
+        for _ in 0..steps.rem_euclid(48) {
+            rate *= PITCH_STEP;
+        }
+        rate
+    }
+
+    /// Fills `buffer` with the 1-bit waveform sampled at `sample_rate`, silent
+    /// while the sound timer is zero. Consecutive calls continue the waveform.
+    pub fn sound_buffer(&mut self, sample_rate: u32, buffer: &mut [bool]) {
+        if self.sound_timer.value == 0 {
+            buffer.fill(false);
+            self.audio_phase = 0.0;
+            return;
+        }
+
+        let step = self.playback_rate() / sample_rate as f32;
+        for sample in buffer {
+            *sample = pattern_bit(&self.audio_pattern, self.audio_phase);
+            self.audio_phase = (self.audio_phase + step) % 128.0;
+        }
+    }
+
+    fn push_audio_event(&mut self, kind: AudioEventKind) {
+        if !self.record_audio_events {
+            return;
+        }
+        self.audio_events.push(AudioEvent {
+            kind,
+            cycle: self.cycles,
+            frame: self.frames,
+        });
+    }
+
+    /// Returns the writes into executed code recorded since the last call.
+    pub fn take_self_modifying_writes(&mut self) -> Vec<SelfModifyingWrite> {
+        core::mem::take(&mut self.self_modifying_writes)
+    }
+
+    fn was_executed(&self, address: u16) -> bool {
+        self.executed
+            .get(address as usize / 64)
+            .is_some_and(|block| block & (1 << (address % 64)) != 0)
+    }
+
+    fn mark_executed(&mut self, address: u16) {
+        self.executed[address as usize / 64] |= 1 << (address % 64);
+    }
+
+    fn wrap_address(&self, address: u16) -> u16 {
+        match self.quirks.wrap_memory {
+            true => (address as usize % self.ram.len()) as u16,
+            false => address,
+        }
+    }
+
+    fn read_memory(&self, address: u16) -> Result<u8, InterpreterError> {
+        let address = self.wrap_address(address);
+        self.ram
+            .read(address)
+            .ok_or(InterpreterError::OutOfBoundsAccess { address })
+    }
+
+    fn write_memory(&mut self, address: u16, value: u8) -> Result<(), InterpreterError> {
+        let address = self.wrap_address(address);
+        if address < 0x200 {
+            match self.memory_protection {
+                MemoryProtection::Allow => {}
+                MemoryProtection::Ignore => return Ok(()),
+                MemoryProtection::Error => {
+                    return Err(InterpreterError::ProtectedWrite { address })
+                }
+            }
+        }
+
+        if self.self_modifying_code != SelfModifyingCode::Ignore && self.was_executed(address) {
+            // The program counter has already moved past the writing instruction
+            let pc = self.program_counter.wrapping_sub(2);
+            if self.self_modifying_code == SelfModifyingCode::Trap {
+                return Err(InterpreterError::SelfModifyingWrite { pc, address });
+            }
+            self.self_modifying_writes
+                .push(SelfModifyingWrite { pc, address });
+        }
+
+        self.ram
+            .write(address, value)
+            .ok_or(InterpreterError::OutOfBoundsAccess { address })
     }
 
-    fn draw(&mut self, register_x: usize, register_y: usize, n: u8) {
+    fn set_sound_timer(&mut self, value: u8) {
+        let sounding = self.sound_timer.value > 0;
+        self.sound_timer.value = value;
+
+        match (sounding, self.sound_timer.value > 0) {
+            (false, true) => self.push_audio_event(AudioEventKind::BeepStart),
+            (true, false) => self.push_audio_event(AudioEventKind::BeepStop),
+            _ => {}
+        }
+    }
+
+    fn draw(
+        &mut self,
+        register_x: usize,
+        register_y: usize,
+        n: u8,
+    ) -> Result<(), InterpreterError> {
         // Fetch coordinates from registers Vx and Vy
         // Note that the coordinates refers to *bit* (pixel) position.
-        let initial_x = self.variable_registers[register_x] & 63; // mod 64
-        let mut y = self.variable_registers[register_y] & 31; // mod 32
+        let initial_x = self.variable_registers[register_x] as usize % self.screen.width();
+        let y = self.variable_registers[register_y] as usize % self.screen.height();
+
+        // Dxy0 draws a 16x16 sprite, two bytes per line, and nothing in classic CHIP-8
+        let (height, bytes_per_line) = match n {
+            #[cfg(feature = "schip")]
+            0 => (16, 2),
+            n => (n as usize, 1),
+        };
 
         // VF will act as a collision detector for sprites.
         // We set it to no collision initially.
         self.variable_registers.clear_vf();
 
-        // Draw each sprite line
-        for sprite_offset in 0..n {
-            // Get sprite line
-            let sprite_address = self.index_register + sprite_offset as u16;
-            let sprite_line = self.ram[sprite_address];
-            let mut x = initial_x;
-
-            // Draw sprite pixels
-            for bit_pos in 0..8 {
-                // Each digit represents a pixel of the sprite line
-                let mask = 0b10000000 >> bit_pos;
-                let bit_digit = sprite_line & mask;
-
-                // If the the sprite pixel is on
-                if bit_digit != 0 {
-                    // Set pixel and detect collision
-                    let collision = self.screen.set_pixel(x, y);
-                    // If collision is detected, set VF.
-                    if collision {
-                        self.variable_registers.set_vf();
-                    }
+        // Each selected plane gets its own sprite, stored one after the other
+        let sprite_size = height * bytes_per_line;
+        let selected_planes = self.screen.selected_planes();
+        let planes = (0..2).filter(|plane| selected_planes & 1 << plane != 0);
+
+        // Draw each sprite line, clipping at the bottom of the screen unless wrapping
+        let screen_height = self.screen.height();
+        let visible_height = match self.quirks.wrap_sprites {
+            true => height,
+            false => height.min(screen_height - y),
+        };
+        for (sprite, plane) in planes.enumerate() {
+            for sprite_offset in 0..visible_height {
+                // Get sprite line, left-aligned in a u128
+                let sprite_address = self
+                    .index_register
+                    .wrapping_add((sprite * sprite_size + sprite_offset * bytes_per_line) as u16);
+                let mut sprite_line = 0u128;
+                for byte in 0..bytes_per_line {
+                    let sprite_byte = self.read_memory(sprite_address.wrapping_add(byte as u16))?;
+                    sprite_line |= (sprite_byte as u128) << (120 - 8 * byte);
                 }
 
-                // If we've reached the horizontal end of the screen, break
-                // otherwise increment x
-                if x == 63 {
-                    break;
-                } else {
-                    x += 1;
+                // XOR the whole line at once and detect collision
+                let collision = self.screen.draw_sprite_line(
+                    plane,
+                    initial_x,
+                    (y + sprite_offset) % screen_height,
+                    sprite_line,
+                    self.quirks.wrap_sprites,
+                );
+                // If collision is detected, set VF.
+                if collision {
+                    self.variable_registers.set_vf();
                 }
             }
+        }
+
+        Ok(())
+    }
+
+    pub fn fetch_instruction(&mut self) -> Result<u16, InterpreterError> {
+        let instruction = self.read_word(self.program_counter)?;
+
+        // Increment program counter
+        self.program_counter = self.program_counter.wrapping_add(2);
+
+        Ok(instruction)
+    }
+
+    fn read_word(&mut self, address: u16) -> Result<u16, InterpreterError> {
+        let start = address as usize;
+
+        // Fetch raw bytes
+        let raw_word: [u8; 2] = self
+            .ram
+            .get(start..start + 2)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(InterpreterError::ProgramCounterOutOfBounds { address })?;
+
+        self.mark_executed(address);
+        self.mark_executed(address.wrapping_add(1));
+
+        // Make 16 bit word out of raw bytes (note the big-endianness)
+        Ok(u16::from_be_bytes(raw_word))
+    }
+
+    fn skip_instruction(&mut self) {
+        // F000 nnnn is four bytes long and must be skipped as a whole
+        let next = self.program_counter as usize;
+        let skip = match self.ram.get(next..next + 2) {
+            #[cfg(feature = "xochip")]
+            Some([0xF0, 0x00]) => 4,
+            _ => 2,
+        };
+        self.program_counter = self.program_counter.wrapping_add(skip);
+    }
+
+    pub fn step(&mut self) -> Result<StepOutcome, InterpreterError> {
+        // The machine stays dead after an error, until a new ROM is loaded
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
+        let result = self.step_inner();
+        if let Err(error) = &result {
+            self.error = Some(error.clone());
+        }
+        result
+    }
+
+    /// Runs one 60 Hz frame: executes up to `instructions_per_frame` instructions, then
+    /// ticks both timers once. Returns what happened over the whole frame.
+    ///
+    /// The frame ends early when idle-loop skipping fast-forwards the timers, the
+    /// skipped frames then already include this frame's tick. It also ends early,
+    /// without ticking, when a breakpoint is hit.
+    pub fn run_frame(
+        &mut self,
+        instructions_per_frame: usize,
+    ) -> Result<StepOutcome, InterpreterError> {
+        let frame = self.execute_frame(instructions_per_frame)?;
+
+        if let Some(mut output) = self.audio_output.take() {
+            output.play_frame(self, &frame);
+            self.audio_output = Some(output);
+        }
+
+        Ok(frame)
+    }
+
+    fn execute_frame(
+        &mut self,
+        instructions_per_frame: usize,
+    ) -> Result<StepOutcome, InterpreterError> {
+        let mut frame = StepOutcome::default();
+        self.apply_key_events();
+
+        for _ in 0..instructions_per_frame {
+            let outcome = self.step()?;
+            frame.cycles += outcome.cycles;
+            frame.screen_changed |= outcome.screen_changed;
+            frame.sound_started |= outcome.sound_started;
+            frame.sound_stopped |= outcome.sound_stopped;
+            frame.waiting_for_key = outcome.waiting_for_key;
+            frame.skipped_frames = outcome.skipped_frames;
+
+            // Timers aren't ticked for a frame interrupted by a breakpoint
+            if outcome.skipped_frames > 0 || self.stopped_at_breakpoint().is_some() {
+                return Ok(frame);
+            }
+        }
 
-            // If we've reached the vertical end of the screen, break
-            // otherwise increment y
-            if y == 31 {
+        // Timers tied to the instruction count have already been ticked by `step`
+        if self.instructions_per_tick.is_none() {
+            let sounding = self.sound_timer.value > 0;
+            self.tick_timers();
+            frame.sound_stopped |= sounding && self.sound_timer.value == 0;
+        }
+
+        Ok(frame)
+    }
+
+    /// Queues a key change to be applied at the start of `event.frame`, or of the next
+    /// frame if that one has already started. Events are latched: a key pressed and
+    /// released in the same frame stays pressed for that whole frame.
+    pub fn queue_key_event(&mut self, event: KeyEvent) {
+        // Stable insertion keeps events for the same frame in arrival order
+        let index = self
+            .key_events
+            .partition_point(|queued| queued.frame <= event.frame);
+        self.key_events.insert(index, event);
+    }
+
+    fn apply_key_events(&mut self) {
+        // Keys pressed by this batch, their release waits for the next frame
+        let mut pressed = 0u16;
+
+        while let Some(event) = self.key_events.front() {
+            let bit = 1 << u8::from(event.key);
+            if event.frame > self.frames || (!event.pressed && pressed & bit != 0) {
                 break;
-            } else {
-                y += 1;
             }
+            if event.pressed {
+                pressed |= bit;
+            }
+            self.input_handler.set(event.key, event.pressed);
+            if self.record_key_latency {
+                self.applied_key_events.push(AppliedKeyEvent {
+                    event: *event,
+                    applied_at: self.frames,
+                });
+            }
+            self.key_events.pop_front();
         }
     }
 
-    pub fn fetch_instruction(&mut self) -> u16 {
-        let instruction_start = self.program_counter as usize;
-        let instruction_end = instruction_start + 2;
+    /// Advances emulated time by `duration`, running as many frames as fit at the
+    /// configured clock. Leftover time is carried over to the next call.
+    pub fn run_for(&mut self, duration: Duration) -> Result<StepOutcome, InterpreterError> {
+        let mut summary = StepOutcome::default();
+        let frame_duration = self.clock.frame_duration();
 
-        // Fetch raw instruction bytes
-        let raw_instruction: [u8; 2] = self.ram[instruction_start..instruction_end]
-            .try_into()
-            .unwrap();
+        self.pending_time += duration;
+        while self.pending_time >= frame_duration {
+            self.pending_time -= frame_duration;
 
-        // Make 16 bit instruction out of raw instruction (note the big-endianness)
-        let instruction = u16::from_be_bytes(raw_instruction);
+            // Skipped frames already had their timer ticks, they are only waited out
+            if self.idle_frames > 0 {
+                self.idle_frames -= 1;
+                continue;
+            }
 
-        // Increment program counter
-        self.program_counter += 2;
+            let instructions = self.clock.instructions_in_frame(self.frames);
+            let frame = self.run_frame(instructions)?;
+            summary.cycles += frame.cycles;
+            summary.screen_changed |= frame.screen_changed;
+            summary.sound_started |= frame.sound_started;
+            summary.sound_stopped |= frame.sound_stopped;
+            summary.waiting_for_key = frame.waiting_for_key;
+            summary.skipped_frames = summary.skipped_frames.saturating_add(frame.skipped_frames);
+            self.idle_frames = frame.skipped_frames.saturating_sub(1);
+
+            // Time spent stopped at a breakpoint isn't caught up on afterwards
+            if self.stopped_at_breakpoint().is_some() {
+                self.pending_time = Duration::ZERO;
+                break;
+            }
+        }
 
-        instruction
+        Ok(summary)
     }
 
-    pub fn step(&mut self) {
+    /// Runs `frames` frames at the clock's rate, as [`Interpreter::run_for`] would over
+    /// that many frame durations. Fast-forwarding frontends call this once per rendered
+    /// frame, so the frames in between are never drawn.
+    pub fn run_frames(&mut self, frames: u32) -> Result<StepOutcome, InterpreterError> {
+        self.run_for(self.clock.frame_duration() * frames)
+    }
+
+    pub fn status(&self) -> Status {
+        if let Some(error) = &self.error {
+            Status::Errored(error.clone())
+        } else if self.halted {
+            Status::Halted
+        } else if self.finished {
+            Status::Finished
+        } else if let Some(address) = self.stopped_at_breakpoint() {
+            Status::BreakpointHit(address)
+        } else if self.paused {
+            Status::Paused
+        } else if let Some(register) = self.input_handler.waiting {
+            Status::WaitingForKey { register }
+        } else {
+            Status::Running
+        }
+    }
+
+    #[cfg(feature = "debug")]
+    fn stopped_at_breakpoint(&self) -> Option<u16> {
+        self.debugger.stopped_at()
+    }
+
+    // There are no breakpoints without the debugger
+    #[cfg(not(feature = "debug"))]
+    fn stopped_at_breakpoint(&self) -> Option<u16> {
+        None
+    }
+
+    fn step_inner(&mut self) -> Result<StepOutcome, InterpreterError> {
+        let mut outcome = StepOutcome::default();
+
+        if self.paused || self.halted || self.finished {
+            return Ok(outcome);
+        }
+        // A pending Fx0A already moved past its own address
+        #[cfg(feature = "debug")]
+        if self.input_handler.waiting.is_none() {
+            if self.should_stop() {
+                return Ok(outcome);
+            }
+            self.save_history();
+        }
+
+        let sounding = self.sound_timer.value > 0;
+        self.run_instruction(&mut outcome)?;
+        let sounding_now = self.sound_timer.value > 0;
+
+        outcome.sound_started = !sounding && sounding_now;
+        outcome.sound_stopped = sounding && !sounding_now;
+        outcome.waiting_for_key = self.input_handler.waiting.is_some();
+
+        Ok(outcome)
+    }
+
+    fn run_instruction(&mut self, outcome: &mut StepOutcome) -> Result<(), InterpreterError> {
+        // Steps spent waiting for a key count too, so timing only depends on the inputs
+        if let Some(instructions_per_tick) = self.instructions_per_tick {
+            if self.steps_since_tick >= instructions_per_tick {
+                self.steps_since_tick = 0;
+                self.tick_timers();
+            }
+            self.steps_since_tick += 1;
+        }
+
         if let Some(register) = self.input_handler.waiting {
-            let Some(key) = self.input_handler.pressed_and_released else {
-                return;
+            let Some(key) = self.resolve_key_wait() else {
+                return Ok(());
             };
             self.variable_registers[register] = key.into();
+            self.input_handler.waiting = None;
+            self.input_handler.pressed_and_released = None;
+        }
+
+        if self.idle_loop_skipping && self.delay_timer.value > 0 && self.at_idle_loop() {
+            // Nothing else happens until the delay timer expires, jump straight to it
+            outcome.skipped_frames = self.delay_timer.value;
+            for _ in 0..outcome.skipped_frames {
+                self.tick_timers();
+            }
         }
 
-        let instruction = self.fetch_instruction();
-        let decoded_instruction = decode(instruction);
+        let instruction = self.fetch_instruction()?;
+        let decoded_instruction = match decode(instruction) {
+            Ok(decoded_instruction) => decoded_instruction,
+            Err(error) => {
+                let Some(mut handler) = self.illegal_instruction_handler.take() else {
+                    return Err(error.into());
+                };
+                let action = handler(self, instruction);
+                self.illegal_instruction_handler = Some(handler);
+                match action {
+                    IllegalInstructionAction::Skip => {}
+                    IllegalInstructionAction::Halt => self.halted = true,
+                    IllegalInstructionAction::Error => return Err(error.into()),
+                }
+                self.cycles += 1;
+                outcome.cycles = 1;
+                return Ok(());
+            }
+        };
+
+        // The hook is taken out while it runs so it can look at the interpreter
+        if let Some(mut hook) = self.hook.take() {
+            let action = hook(self, &decoded_instruction);
+            self.hook = Some(hook);
+            match action {
+                HookAction::Continue => {}
+                HookAction::Skip => {
+                    // F000 nnnn is skipped as a whole, its operand must not run as an opcode
+                    if let Instruction::SetIndexLong = decoded_instruction {
+                        self.program_counter = self.program_counter.wrapping_add(2);
+                    }
+                    // Like a skipped illegal instruction, it still takes its cycle
+                    self.cycles += 1;
+                    outcome.cycles = 1;
+                    return Ok(());
+                }
+                HookAction::Abort => {
+                    return Err(InterpreterError::Aborted {
+                        address: self.program_counter.wrapping_sub(2),
+                    })
+                }
+            }
+        }
+
+        self.execute(decoded_instruction)?;
+        self.cycles += 1;
+
+        if let Some(mut post_hook) = self.post_hook.take() {
+            post_hook(self, &decoded_instruction);
+            self.post_hook = Some(post_hook);
+        }
 
-        self.execute(decoded_instruction);
+        outcome.cycles = 1;
+        outcome.screen_changed = matches!(
+            decoded_instruction,
+            Instruction::ClearScreen
+                | Instruction::Draw { .. }
+                | Instruction::ScrollDown { .. }
+                | Instruction::ScrollRight
+                | Instruction::ScrollLeft
+                | Instruction::LowResolution
+                | Instruction::HighResolution
+        ) || self.is_two_page_clear(&decoded_instruction);
+
+        Ok(())
+    }
+
+    fn resolve_key_wait(&mut self) -> Option<Key> {
+        // Keys held before the wait started are AlreadyPressed, Pressed ones are new presses
+        let pressed = self
+            .input_handler
+            .keys_state
+            .iter()
+            .position(|state| matches!(state, KeyState::Pressed))
+            .map(|key| Key::from(key as u8));
+
+        match self.quirks.key_wait {
+            KeyWait::Press => pressed,
+            KeyWait::Release => self.input_handler.pressed_and_released,
+            KeyWait::ReleaseWithTone => {
+                // The original interpreter beeps for as long as the key is held
+                if pressed.is_some() {
+                    self.set_sound_timer(self.sound_timer.value.max(4));
+                }
+                self.input_handler.pressed_and_released
+            }
+        }
+    }
+
+    // Detects the `LD Vx, DT; SE Vx, 0; JP <loop start>` idiom at the program counter
+    fn at_idle_loop(&self) -> bool {
+        let pc = self.program_counter as usize;
+        let Some(&[load_high, load_low, skip_high, skip_low, jump_high, jump_low]) =
+            self.ram.get(pc..pc + 6)
+        else {
+            return false;
+        };
+
+        let load = decode(u16::from_be_bytes([load_high, load_low]));
+        let skip = decode(u16::from_be_bytes([skip_high, skip_low]));
+        let jump = decode(u16::from_be_bytes([jump_high, jump_low]));
+
+        matches!(
+            (load, skip, jump),
+            (
+                Ok(Instruction::SetVariableWithDelayTimer { register }),
+                Ok(Instruction::SkipEqualByte { register: skip_register, byte: 0 }),
+                Ok(Instruction::Jump { address }),
+            ) if register == skip_register && address == self.program_counter
+        )
     }
 
-    fn execute(&mut self, instruction: Instruction) {
+    fn execute(&mut self, instruction: Instruction) -> Result<(), InterpreterError> {
         match instruction {
             // Subroutines
             Instruction::Call { address } => {
+                if self.stack.depth() >= self.max_stack_depth {
+                    return Err(InterpreterError::StackOverflow {
+                        address: self.program_counter.wrapping_sub(2),
+                    });
+                }
                 self.stack.push(self.program_counter);
                 self.program_counter = address;
             }
-            Instruction::Return => self.program_counter = self.stack.pop(),
+            Instruction::Return => {
+                self.program_counter =
+                    self.stack.pop().ok_or(InterpreterError::StackUnderflow {
+                        address: self.program_counter.wrapping_sub(2),
+                    })?;
+            }
 
             // Control flow
-            Instruction::Jump { address } => self.program_counter = address,
+            Instruction::Jump { address } => {
+                // The program counter has already moved past the jump
+                self.finished = address == self.program_counter.wrapping_sub(2);
+                self.program_counter = address;
+            }
             Instruction::JumpOffset {
                 base_address,
-                #[cfg(feature = "modern")]
                 register,
             } => {
-                #[cfg(not(feature = "modern"))]
-                let register = 0usize;
-
+                let register = if self.quirks.jump_uses_vx {
+                    register
+                } else {
+                    0
+                };
                 self.program_counter = base_address + self.variable_registers[register] as u16
             }
             Instruction::SkipEqualByte { register, byte } => {
                 if self.variable_registers[register] == byte {
-                    self.program_counter += 2;
+                    self.skip_instruction();
                 }
             }
             Instruction::SkipNotEqualByte { register, byte } => {
                 if self.variable_registers[register] != byte {
-                    self.program_counter += 2;
+                    self.skip_instruction();
                 }
             }
             Instruction::SkipEqualVariable {
@@ -242,7 +1829,7 @@ impl Interpreter {
                 register_y,
             } => {
                 if self.variable_registers[register_x] == self.variable_registers[register_y] {
-                    self.program_counter += 2;
+                    self.skip_instruction();
                 }
             }
             Instruction::SkipNotEqualVariable {
@@ -250,19 +1837,20 @@ impl Interpreter {
                 register_y,
             } => {
                 if self.variable_registers[register_x] != self.variable_registers[register_y] {
-                    self.program_counter += 2;
+                    self.skip_instruction();
                 }
             }
+            // Only the low nibble of Vx names a key, like the font instructions
             Instruction::SkipKey { register } => {
-                let key_index = self.variable_registers[register] as usize;
+                let key_index = (self.variable_registers[register] & 0b0000_1111) as usize;
                 if let KeyState::Pressed = self.input_handler.keys_state[key_index] {
-                    self.program_counter += 2;
+                    self.skip_instruction();
                 }
             }
             Instruction::SkipNotKey { register } => {
-                let key_index = self.variable_registers[register] as usize;
+                let key_index = (self.variable_registers[register] & 0b0000_1111) as usize;
                 if !matches!(self.input_handler.keys_state[key_index], KeyState::Pressed) {
-                    self.program_counter += 2;
+                    self.skip_instruction();
                 }
             }
 
@@ -273,9 +1861,17 @@ impl Interpreter {
                 register_y,
             } => self.variable_registers[register_x] = self.variable_registers[register_y],
             Instruction::SetIndexWithAddress { address } => self.index_register = address,
+            Instruction::SetIndexLong => {
+                self.index_register = self.read_word(self.program_counter)?;
+                self.program_counter = self.program_counter.wrapping_add(2);
+            }
             Instruction::SetIndexWithFontAddress { register } => {
                 self.index_register = (self.variable_registers[register] & 0b0000_1111) as u16 * 5;
             }
+            Instruction::SetIndexWithBigFontAddress { register } => {
+                let digit = self.variable_registers[register] & 0b0000_1111;
+                self.index_register = BIG_FONT_ADDRESS + digit as u16 * 10;
+            }
 
             // Arithmetic operations
             Instruction::AddWithByte { register, byte } => {
@@ -295,7 +1891,9 @@ impl Interpreter {
                 self.variable_registers.set_vf_to(overflow as u8);
             }
             Instruction::AddIndexWithVariable { register } => {
-                self.index_register += self.variable_registers[register] as u16;
+                self.index_register = self
+                    .index_register
+                    .wrapping_add(self.variable_registers[register] as u16);
             }
             Instruction::SubWithVariable {
                 register_x,
@@ -323,11 +1921,9 @@ impl Interpreter {
             }
             Instruction::ShiftRight {
                 register_x,
-                #[cfg(not(feature = "modern"))]
                 register_y,
             } => {
-                #[cfg(not(feature = "modern"))]
-                {
+                if self.quirks.shift_reads_vy {
                     // Set Vx to Vy
                     self.variable_registers[register_x] = self.variable_registers[register_y];
                 }
@@ -340,11 +1936,9 @@ impl Interpreter {
             }
             Instruction::ShiftLeft {
                 register_x,
-                #[cfg(not(feature = "modern"))]
                 register_y,
             } => {
-                #[cfg(not(feature = "modern"))]
-                {
+                if self.quirks.shift_reads_vy {
                     // Set Vx to Vy
                     self.variable_registers[register_x] = self.variable_registers[register_y];
                 }
@@ -362,27 +1956,43 @@ impl Interpreter {
                 register_y,
             } => {
                 self.variable_registers[register_x] |= self.variable_registers[register_y];
+                if self.quirks.vf_reset {
+                    self.variable_registers.clear_vf();
+                }
             }
             Instruction::And {
                 register_x,
                 register_y,
             } => {
                 self.variable_registers[register_x] &= self.variable_registers[register_y];
+                if self.quirks.vf_reset {
+                    self.variable_registers.clear_vf();
+                }
             }
             Instruction::Xor {
                 register_x,
                 register_y,
             } => {
                 self.variable_registers[register_x] ^= self.variable_registers[register_y];
+                if self.quirks.vf_reset {
+                    self.variable_registers.clear_vf();
+                }
             }
 
             // Display
             Instruction::ClearScreen => self.screen.clear(),
+            Instruction::ScrollDown { n } => self.screen.scroll_down(n as usize),
+            Instruction::ScrollRight => self.screen.scroll_right(4),
+            Instruction::ScrollLeft => self.screen.scroll_left(4),
+            Instruction::SelectPlanes { mask } => self.screen.select_planes(mask),
+            Instruction::LowResolution => self.screen.set_resolution(Resolution::Low),
+            Instruction::HighResolution => self.screen.set_resolution(Resolution::High),
+            Instruction::Exit => self.halted = true,
             Instruction::Draw {
                 register_x,
                 register_y,
                 n,
-            } => self.draw(register_x, register_y, n),
+            } => self.draw(register_x, register_y, n)?,
 
             // Timers
             Instruction::SetVariableWithDelayTimer { register } => {
@@ -391,39 +2001,67 @@ impl Interpreter {
             Instruction::SetDelayTimer { register } => {
                 self.delay_timer.value = self.variable_registers[register];
             }
+            Instruction::SetPitch { register } => {
+                let pitch = self.variable_registers[register];
+                if pitch != self.pitch {
+                    self.pitch = pitch;
+                    self.push_audio_event(AudioEventKind::PitchChanged { pitch });
+                }
+            }
             Instruction::SetSoundTimer { register } => {
-                self.sound_timer.value = self.variable_registers[register];
+                self.set_sound_timer(self.variable_registers[register]);
             }
 
             // RAM load and store
             Instruction::StoreRegisters { up_to_register } => {
-                #[cfg(feature = "modern")]
                 let index_register = self.index_register;
 
                 for register in 0..=up_to_register {
-                    self.ram[self.index_register] = self.variable_registers[register];
-                    self.index_register += 1;
+                    self.write_memory(self.index_register, self.variable_registers[register])?;
+                    self.index_register = self.index_register.wrapping_add(1);
                 }
 
-                #[cfg(feature = "modern")]
-                {
+                if !self.quirks.increment_index {
                     self.index_register = index_register;
                 }
             }
+            Instruction::LoadAudioPattern => {
+                let mut pattern = [0; 16];
+                for (offset, byte) in pattern.iter_mut().enumerate() {
+                    *byte = self.read_memory(self.index_register.wrapping_add(offset as u16))?;
+                }
+                if pattern != self.audio_pattern {
+                    self.audio_pattern = pattern;
+                    self.push_audio_event(AudioEventKind::PatternChanged { pattern });
+                }
+            }
             Instruction::LoadIntoRegisters { up_to_register } => {
-                #[cfg(feature = "modern")]
                 let index_register = self.index_register;
 
                 for register in 0..=up_to_register {
-                    self.variable_registers[register] = self.ram[self.index_register];
-                    self.index_register += 1;
+                    self.variable_registers[register] = self.read_memory(self.index_register)?;
+                    self.index_register = self.index_register.wrapping_add(1);
                 }
 
-                #[cfg(feature = "modern")]
-                {
+                if !self.quirks.increment_index {
                     self.index_register = index_register;
                 }
             }
+            Instruction::StoreFlags { up_to_register } => {
+                let mut flags = self.flag_storage.load();
+                for (register, flag) in flags.iter_mut().enumerate().take(up_to_register + 1) {
+                    *flag = self.variable_registers[register];
+                }
+                self.flag_storage.save(&flags);
+                self.flags = flags;
+            }
+            Instruction::LoadFlags { up_to_register } => {
+                let flags = self.flag_storage.load();
+                self.flags = flags;
+                for (register, flag) in flags.into_iter().enumerate().take(up_to_register + 1) {
+                    self.variable_registers[register] = flag;
+                }
+            }
 
             // Misc
             Instruction::StoreDecimalConversion { register } => {
@@ -432,27 +2070,27 @@ impl Interpreter {
                 let tens = (value - (hundreds * 100)) / 10;
                 let ones = value - (hundreds * 100) - (tens * 10);
 
-                self.ram[self.index_register] = hundreds;
-                self.ram[self.index_register + 1] = tens;
-                self.ram[self.index_register + 2] = ones;
-            }
-            Instruction::WaitForKey { register } => {
-                self.input_handler.keys_state =
-                    self.input_handler.keys_state.map(|state| match state {
-                        KeyState::NotPressed => state,
-                        KeyState::Pressed => KeyState::AlreadyPressed,
-                        KeyState::AlreadyPressed => state,
-                    });
-                self.input_handler.waiting = Some(register);
+                self.write_memory(self.index_register, hundreds)?;
+                self.write_memory(self.index_register.wrapping_add(1), tens)?;
+                self.write_memory(self.index_register.wrapping_add(2), ones)?;
             }
+            Instruction::WaitForKey { register } => self.input_handler.start_waiting(register),
             Instruction::RandomAnd { register, byte } => {
-                let mut rng = OsRng;
-                let random_byte = rng.gen::<u8>();
+                let random_byte = self.rng.gen::<u8>();
                 self.variable_registers[register] = random_byte & byte;
             }
 
+            instruction if self.is_two_page_clear(&instruction) => self.screen.clear(),
             // Defunct
-            Instruction::MachineRoutine { .. } => {}
+            Instruction::MachineRoutine { address } => {
+                if let Some(mut handler) = self.machine_routine_handler.take() {
+                    let result = handler(self, address);
+                    self.machine_routine_handler = Some(handler);
+                    result?;
+                }
+            }
         }
+
+        Ok(())
     }
 }
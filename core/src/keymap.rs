@@ -0,0 +1,159 @@
+use alloc::string::ToString;
+use core::str::FromStr;
+
+use crate::{error::LayoutError, interpreter::Key};
+
+// Physical layout of the COSMAC VIP hex keypad
+#[rustfmt::skip]
+pub const KEYPAD: [[Key; 4]; 4] = [
+    [Key::Key1, Key::Key2, Key::Key3, Key::KeyC],
+    [Key::Key4, Key::Key5, Key::Key6, Key::KeyD],
+    [Key::Key7, Key::Key8, Key::Key9, Key::KeyE],
+    [Key::KeyA, Key::Key0, Key::KeyB, Key::KeyF],
+];
+
+// PC set 1 scancode of the leftmost key of each keypad row (1, Q, A and Z)
+const SCANCODE_ROWS: [u32; 4] = [0x02, 0x10, 0x1E, 0x2C];
+
+/// Returns the key at the physical position of a PC set 1 scancode, as reported by
+/// Windows and Linux evdev. Scancodes don't depend on the active layout.
+pub fn scancode_key(scancode: u32) -> Option<Key> {
+    SCANCODE_ROWS
+        .iter()
+        .zip(KEYPAD)
+        .find_map(|(&first, row)| row.get(scancode.checked_sub(first)? as usize).copied())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layout {
+    Qwerty,
+    Azerty,
+    Qwertz,
+    Dvorak,
+    Colemak,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 5] = [
+        Layout::Qwerty,
+        Layout::Azerty,
+        Layout::Qwertz,
+        Layout::Dvorak,
+        Layout::Colemak,
+    ];
+}
+
+/// Parses a layout name such as `qwerty` or `Dvorak`, ignoring ASCII case.
+impl FromStr for Layout {
+    type Err = LayoutError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "qwerty" => Ok(Layout::Qwerty),
+            "azerty" => Ok(Layout::Azerty),
+            "qwertz" => Ok(Layout::Qwertz),
+            "dvorak" => Ok(Layout::Dvorak),
+            "colemak" => Ok(Layout::Colemak),
+            _ => Err(LayoutError::Unknown(name.to_string())),
+        }
+    }
+}
+
+/// Maps host keyboard characters to keypad keys.
+///
+/// The keypad is laid out positionally over the 1234/QWER/ASDF/ZXCV block of a
+/// QWERTY keyboard. Presets for other layouts use the characters found at those
+/// same physical positions, so the keypad shape is preserved.
+#[derive(Clone, Copy, Debug)]
+pub struct Keymap {
+    // Character bound to each key, indexed by key value
+    characters: [char; 16],
+}
+
+impl Keymap {
+    pub fn new(characters: [char; 16]) -> Self {
+        Self { characters }
+    }
+
+    // `rows` holds the characters found over the 4x4 keypad, top row first
+    const fn from_rows(rows: [[char; 4]; 4]) -> Self {
+        // Keypad (COSMAC VIP layout):
+        // 1 2 3 C
+        // 4 5 6 D
+        // 7 8 9 E
+        // A 0 B F
+        #[rustfmt::skip]
+        let characters = [
+            rows[3][1], rows[0][0], rows[0][1], rows[0][2],
+            rows[1][0], rows[1][1], rows[1][2], rows[2][0],
+            rows[2][1], rows[2][2], rows[3][0], rows[3][2],
+            rows[0][3], rows[1][3], rows[2][3], rows[3][3],
+        ];
+        Self { characters }
+    }
+
+    pub const QWERTY: Keymap = Keymap::from_rows([
+        ['1', '2', '3', '4'],
+        ['q', 'w', 'e', 'r'],
+        ['a', 's', 'd', 'f'],
+        ['z', 'x', 'c', 'v'],
+    ]);
+
+    // Number row characters are the unshifted ones
+    pub const AZERTY: Keymap = Keymap::from_rows([
+        ['&', 'é', '"', '\''],
+        ['a', 'z', 'e', 'r'],
+        ['q', 's', 'd', 'f'],
+        ['w', 'x', 'c', 'v'],
+    ]);
+
+    pub const QWERTZ: Keymap = Keymap::from_rows([
+        ['1', '2', '3', '4'],
+        ['q', 'w', 'e', 'r'],
+        ['a', 's', 'd', 'f'],
+        ['y', 'x', 'c', 'v'],
+    ]);
+
+    pub const DVORAK: Keymap = Keymap::from_rows([
+        ['1', '2', '3', '4'],
+        ['\'', ',', '.', 'p'],
+        ['a', 'o', 'e', 'u'],
+        [';', 'q', 'j', 'k'],
+    ]);
+
+    pub const COLEMAK: Keymap = Keymap::from_rows([
+        ['1', '2', '3', '4'],
+        ['q', 'w', 'f', 'p'],
+        ['a', 'r', 's', 't'],
+        ['z', 'x', 'c', 'v'],
+    ]);
+
+    pub fn preset(layout: Layout) -> Self {
+        match layout {
+            Layout::Qwerty => Self::QWERTY,
+            Layout::Azerty => Self::AZERTY,
+            Layout::Qwertz => Self::QWERTZ,
+            Layout::Dvorak => Self::DVORAK,
+            Layout::Colemak => Self::COLEMAK,
+        }
+    }
+
+    /// Returns the key bound to a character, ignoring ASCII case.
+    pub fn key(&self, character: char) -> Option<Key> {
+        let character = character.to_ascii_lowercase();
+        self.characters
+            .iter()
+            .position(|&bound| bound == character)
+            .map(|index| Key::from(index as u8))
+    }
+
+    pub fn character(&self, key: Key) -> char {
+        self.characters[u8::from(key) as usize]
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::QWERTY
+    }
+}
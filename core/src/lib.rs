@@ -1,3 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod audio;
+pub mod battery;
 pub mod core;
+#[cfg(feature = "debug")]
+pub mod debug;
+#[cfg(feature = "debug")]
+pub mod decompiler;
+#[cfg(feature = "debug")]
+pub mod disassembler;
+pub mod error;
+pub mod flags;
+mod hash;
 pub mod instructions;
 pub mod interpreter;
+pub mod keymap;
+pub mod patch;
+pub mod replay;
+pub mod rewind;
+#[cfg(feature = "tokio")]
+pub mod runner;
+mod savestate;
+pub mod scores;
+#[cfg(feature = "serde")]
+mod serde_arrays;
@@ -0,0 +1,293 @@
+use alloc::vec::Vec;
+
+use crate::{error::PatchError, hash::crc32};
+
+const HEADER: &[u8] = b"PATCH";
+const BPS_HEADER: &[u8] = b"BPS1";
+// Source, target and patch CRC-32s
+const BPS_FOOTER_SIZE: usize = 12;
+const FOOTER: &[u8] = b"EOF";
+// Records can't start at this offset, it would be read as the footer
+const FOOTER_OFFSET: usize = 0x454F46;
+const MAX_RECORD_SIZE: usize = 0xFFFF;
+
+/// Applies an IPS or BPS patch to a ROM, telling them apart by their header.
+pub fn apply_patch(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    if patch.starts_with(BPS_HEADER) {
+        apply_bps(rom, patch)
+    } else {
+        apply_ips(rom, patch)
+    }
+}
+
+/// Applies an IPS patch to a ROM, returning the patched ROM.
+///
+/// Records past the end of the ROM grow it, and the truncation extension
+/// (a 3 byte size after the footer) is honored.
+pub fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut records = patch
+        .strip_prefix(HEADER)
+        .ok_or(PatchError::InvalidHeader)?;
+    let mut patched = rom.to_vec();
+
+    loop {
+        if let Some(rest) = records.strip_prefix(FOOTER) {
+            if let [a, b, c] = *rest {
+                patched.truncate(u32::from_be_bytes([0, a, b, c]) as usize);
+            }
+            return Ok(patched);
+        }
+
+        let [a, b, c, d, e, ref rest @ ..] = *records else {
+            return Err(PatchError::Truncated);
+        };
+        let offset = u32::from_be_bytes([0, a, b, c]) as usize;
+        let size = u16::from_be_bytes([d, e]) as usize;
+
+        // Zero sized records are run-length encoded
+        let (data, rest) = if size == 0 {
+            let [d, e, value, ref rest @ ..] = *rest else {
+                return Err(PatchError::Truncated);
+            };
+            let size = u16::from_be_bytes([d, e]) as usize;
+            (RecordData::Run { size, value }, rest)
+        } else {
+            let data = rest.get(..size).ok_or(PatchError::Truncated)?;
+            (RecordData::Bytes(data), &rest[size..])
+        };
+
+        let end = offset + data.len();
+        if patched.len() < end {
+            patched.resize(end, 0);
+        }
+        match data {
+            RecordData::Bytes(data) => patched[offset..end].copy_from_slice(data),
+            RecordData::Run { value, .. } => patched[offset..end].fill(value),
+        }
+
+        records = rest;
+    }
+}
+
+/// Creates an IPS patch turning `original` into `modified`.
+pub fn create_ips(original: &[u8], modified: &[u8]) -> Result<Vec<u8>, PatchError> {
+    if modified.len() > FOOTER_OFFSET {
+        return Err(PatchError::TooLarge {
+            size: modified.len(),
+        });
+    }
+
+    let mut patch = HEADER.to_vec();
+    let differs = |offset: usize| original.get(offset) != modified.get(offset);
+
+    let mut offset = 0;
+    while offset < modified.len() {
+        if !differs(offset) {
+            offset += 1;
+            continue;
+        }
+
+        let mut end = offset;
+        while end < modified.len() && end - offset < MAX_RECORD_SIZE && differs(end) {
+            end += 1;
+        }
+
+        let data = &modified[offset..end];
+        patch.extend_from_slice(&(offset as u32).to_be_bytes()[1..]);
+        patch.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        patch.extend_from_slice(data);
+
+        offset = end;
+    }
+
+    patch.extend_from_slice(FOOTER);
+    if modified.len() < original.len() {
+        patch.extend_from_slice(&(modified.len() as u32).to_be_bytes()[1..]);
+    }
+
+    Ok(patch)
+}
+
+/// Applies a BPS patch to a ROM, returning the patched ROM.
+///
+/// The ROM, the patch and the result are checked against the patch's CRC-32s.
+pub fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut reader = BpsReader(
+        patch
+            .strip_prefix(BPS_HEADER)
+            .ok_or(PatchError::InvalidHeader)?,
+    );
+    let footer = patch
+        .len()
+        .checked_sub(BPS_FOOTER_SIZE)
+        .filter(|&footer| footer >= BPS_HEADER.len())
+        .ok_or(PatchError::Truncated)?;
+    let checksum = |offset: usize| {
+        let bytes = &patch[footer + offset..footer + offset + 4];
+        u32::from_le_bytes(bytes.try_into().unwrap())
+    };
+    if crc32(&patch[..footer + 8]) != checksum(8) {
+        return Err(PatchError::ChecksumMismatch);
+    }
+    if crc32(rom) != checksum(0) {
+        return Err(PatchError::SourceMismatch);
+    }
+    reader.0 = &reader.0[..footer - BPS_HEADER.len()];
+
+    let source_size = reader.number()?;
+    let target_size = reader.number()?;
+    let metadata_size = reader.number()?;
+    reader.bytes(metadata_size)?;
+    if source_size != rom.len() {
+        return Err(PatchError::SourceMismatch);
+    }
+
+    // Not preallocated, the sizes come from the patch
+    let mut target = Vec::new();
+    let (mut source_offset, mut target_offset) = (0usize, 0usize);
+    while !reader.0.is_empty() {
+        let action = reader.number()?;
+        let length = (action >> 2) + 1;
+        if length > target_size - target.len() {
+            return Err(PatchError::OutOfBounds);
+        }
+        match action & 0b11 {
+            // Source read: the bytes at the same offset in the ROM
+            0 => {
+                let start = target.len();
+                let bytes = rom
+                    .get(start..start + length)
+                    .ok_or(PatchError::OutOfBounds)?;
+                target.extend_from_slice(bytes);
+            }
+            // Target read: bytes stored in the patch
+            1 => target.extend_from_slice(reader.bytes(length)?),
+            // Source copy: bytes anywhere in the ROM, relative to the last copy
+            2 => {
+                source_offset = reader.offset(source_offset)?;
+                let bytes = rom
+                    .get(source_offset..source_offset + length)
+                    .ok_or(PatchError::OutOfBounds)?;
+                target.extend_from_slice(bytes);
+                source_offset += length;
+            }
+            // Target copy: bytes already written, which the copy may itself be producing
+            _ => {
+                target_offset = reader.offset(target_offset)?;
+                if target_offset >= target.len() {
+                    return Err(PatchError::OutOfBounds);
+                }
+                for _ in 0..length {
+                    target.push(target[target_offset]);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    if target.len() != target_size || crc32(&target) != checksum(4) {
+        return Err(PatchError::TargetMismatch);
+    }
+    Ok(target)
+}
+
+/// Creates a BPS patch turning `original` into `modified`.
+///
+/// Changed bytes are stored as they are, the patch doesn't look for data moved
+/// around within the ROM.
+pub fn create_bps(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let mut patch = BPS_HEADER.to_vec();
+    write_number(&mut patch, original.len());
+    write_number(&mut patch, modified.len());
+    write_number(&mut patch, 0);
+
+    let same = |offset: usize| original.get(offset) == modified.get(offset);
+    let mut offset = 0;
+    while offset < modified.len() {
+        let unchanged = same(offset);
+        let end = (offset..modified.len())
+            .find(|&end| same(end) != unchanged)
+            .unwrap_or(modified.len());
+
+        let action = if unchanged { 0 } else { 1 };
+        write_number(&mut patch, (end - offset - 1) << 2 | action);
+        if !unchanged {
+            patch.extend_from_slice(&modified[offset..end]);
+        }
+        offset = end;
+    }
+
+    patch.extend_from_slice(&crc32(original).to_le_bytes());
+    patch.extend_from_slice(&crc32(modified).to_le_bytes());
+    let checksum = crc32(&patch);
+    patch.extend_from_slice(&checksum.to_le_bytes());
+    patch
+}
+
+// BPS numbers are variable length, 7 bits per byte, the last byte having its top bit set
+fn write_number(patch: &mut Vec<u8>, mut number: usize) {
+    loop {
+        let bits = (number & 0x7F) as u8;
+        number >>= 7;
+        if number == 0 {
+            patch.push(0x80 | bits);
+            return;
+        }
+        patch.push(bits);
+        number -= 1;
+    }
+}
+
+struct BpsReader<'a>(&'a [u8]);
+
+impl<'a> BpsReader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], PatchError> {
+        if length > self.0.len() {
+            return Err(PatchError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn number(&mut self) -> Result<usize, PatchError> {
+        let (mut number, mut shift) = (0usize, 1usize);
+        loop {
+            let byte = self.bytes(1)?[0];
+            number = (byte as usize & 0x7F)
+                .checked_mul(shift)
+                .and_then(|bits| number.checked_add(bits))
+                .ok_or(PatchError::OutOfBounds)?;
+            if byte & 0x80 != 0 {
+                return Ok(number);
+            }
+            shift = shift.checked_mul(0x80).ok_or(PatchError::OutOfBounds)?;
+            number = number.checked_add(shift).ok_or(PatchError::OutOfBounds)?;
+        }
+    }
+
+    // Copy offsets are relative, the lowest bit giving the direction
+    fn offset(&mut self, base: usize) -> Result<usize, PatchError> {
+        let number = self.number()?;
+        let distance = number >> 1;
+        let offset = match number & 1 {
+            0 => base.checked_add(distance),
+            _ => base.checked_sub(distance),
+        };
+        offset.ok_or(PatchError::OutOfBounds)
+    }
+}
+
+enum RecordData<'a> {
+    Bytes(&'a [u8]),
+    Run { size: usize, value: u8 },
+}
+
+impl RecordData<'_> {
+    fn len(&self) -> usize {
+        match self {
+            RecordData::Bytes(data) => data.len(),
+            RecordData::Run { size, .. } => *size,
+        }
+    }
+}
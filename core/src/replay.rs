@@ -0,0 +1,167 @@
+use alloc::vec::Vec;
+
+use crate::{
+    error::{LoadError, ReplayError},
+    interpreter::{Interpreter, KeyEvent, Quirks},
+};
+
+/// Where two playbacks of a movie first disagree, see [`Movie::compare`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// First frame after which the states differ.
+    pub frame: u64,
+    /// Address and opcode of the first instruction after which the states differ.
+    /// `None` when the instructions agree and only the end of the frame differs.
+    pub instruction: Option<(u16, u16)>,
+}
+
+/// A recorded run: the settings and keypad input that reproduce it exactly.
+///
+/// Runs are recorded and played back on deterministic interpreters, driven by
+/// `run_frame(instructions_per_frame)` once per frame. Calling [`Movie::checkpoint`]
+/// after each recorded frame and [`Movie::verify`] after each played one catches a
+/// playback going out of sync, e.g. because of different quirks or generator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Movie {
+    pub seed: u64,
+    pub instructions_per_frame: u32,
+    pub quirks: Quirks,
+    // Key changes in the order they were queued, stamped with the frame they apply at
+    pub events: Vec<KeyEvent>,
+    // State hash stored every this many frames while recording, 0 stores none
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub checksum_interval: u32,
+    // State hashes after the frames they're stamped with, in frame order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub checksums: Vec<(u64, u64)>,
+}
+
+impl Movie {
+    pub fn new(seed: u64, instructions_per_frame: u32, quirks: Quirks) -> Self {
+        Self {
+            seed,
+            instructions_per_frame,
+            quirks,
+            events: Vec::new(),
+            checksum_interval: 60,
+            checksums: Vec::new(),
+        }
+    }
+
+    /// Builds the interpreter to record on.
+    pub fn start(&self, program: &[u8]) -> Result<Interpreter, LoadError> {
+        let mut interpreter = Interpreter::builder()
+            .deterministic(self.seed, self.instructions_per_frame)
+            .build(program)?;
+        interpreter.quirks = self.quirks;
+        Ok(interpreter)
+    }
+
+    /// Queues a key change on the interpreter and records it.
+    pub fn record(&mut self, interpreter: &mut Interpreter, mut event: KeyEvent) {
+        // Events for past frames apply at the current one, which is what playback needs
+        event.frame = event.frame.max(interpreter.frames());
+        interpreter.queue_key_event(event);
+        self.events.push(event);
+    }
+
+    /// Stores the state hash if the interpreter is at a checksum frame. Called after
+    /// each recorded frame.
+    pub fn checkpoint(&mut self, interpreter: &Interpreter) {
+        let frame = interpreter.frames();
+        let interval = self.checksum_interval as u64;
+        // Each frame is stored once, the initial state never is
+        let new = self.checksums.last().is_none_or(|&(last, _)| last < frame);
+        if interval > 0 && frame > 0 && frame.is_multiple_of(interval) && new {
+            self.checksums.push((frame, interpreter.state_hash()));
+        }
+    }
+
+    /// Checks the state against the checksum stored for the interpreter's frame, if
+    /// any. Called after each played frame, the first error is at most
+    /// `checksum_interval` frames after the one the runs went apart at.
+    pub fn verify(&self, interpreter: &Interpreter) -> Result<(), ReplayError> {
+        let frame = interpreter.frames();
+        match self
+            .checksums
+            .binary_search_by_key(&frame, |&(frame, _)| frame)
+        {
+            Ok(index) if self.checksums[index].1 != interpreter.state_hash() => {
+                Err(ReplayError::Desync { frame })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Builds an interpreter with every recorded event queued, which replays the run
+    /// when driven like the recording was.
+    pub fn play(&self, program: &[u8]) -> Result<Interpreter, LoadError> {
+        let mut interpreter = self.start(program)?;
+        for &event in &self.events {
+            interpreter.queue_key_event(event);
+        }
+        Ok(interpreter)
+    }
+
+    /// Plays the movie twice for up to `frames` frames, once with its own quirks and
+    /// once with `other`, and returns where the two runs first differ. State hashes
+    /// are compared after every frame, and the first frame that differs is replayed
+    /// instruction by instruction. An error in one run only counts as a difference,
+    /// the same error in both ends the comparison.
+    pub fn compare(
+        &self,
+        program: &[u8],
+        other: Quirks,
+        frames: u64,
+    ) -> Result<Option<Divergence>, LoadError> {
+        let instructions = self.instructions_per_frame as usize;
+        let mut left = self.play(program)?;
+        let mut right = self.play(program)?;
+        right.quirks = other;
+
+        for frame in 0..frames {
+            let starts = (left.clone(), right.clone());
+            let results = (left.run_frame(instructions), right.run_frame(instructions));
+            let same_state = left.state_hash() == right.state_hash();
+            match results {
+                (Ok(_), Ok(_)) if same_state => continue,
+                (Err(left_error), Err(right_error)) if same_state && left_error == right_error => {
+                    break
+                }
+                _ => {}
+            }
+
+            return Ok(Some(Divergence {
+                frame,
+                instruction: first_divergent_instruction(starts.0, starts.1, instructions),
+            }));
+        }
+        Ok(None)
+    }
+}
+
+// Replays a frame that ended in different states one instruction at a time
+fn first_divergent_instruction(
+    mut left: Interpreter,
+    mut right: Interpreter,
+    instructions: usize,
+) -> Option<(u16, u16)> {
+    for step in 0..instructions {
+        let address = left.program_counter;
+        let opcode = u16::from_be_bytes([
+            left.ram.read(address)?,
+            left.ram.read(address.wrapping_add(1))?,
+        ]);
+        // The frame's key events are applied before its first instruction
+        let results = if step == 0 {
+            (left.run_frame(1).err(), right.run_frame(1).err())
+        } else {
+            (left.step().err(), right.step().err())
+        };
+        if results.0 != results.1 || left.state_hash() != right.state_hash() {
+            return Some((address, opcode));
+        }
+    }
+    None
+}
@@ -0,0 +1,69 @@
+use alloc::collections::VecDeque;
+
+use crate::{interpreter::Interpreter, savestate::Snapshot};
+
+/// Snapshots an interpreter every few frames into a bounded history, so it can be
+/// rewound. At 60 frames per second, a snapshot every 6 frames and 3000 of them
+/// cover five minutes in about 20 MB.
+pub struct Rewind {
+    // Frames between two snapshots
+    interval: u64,
+    capacity: usize,
+    // Snapshots along with the frame they were taken at, oldest first
+    snapshots: VecDeque<(u64, Snapshot)>,
+}
+
+impl Rewind {
+    pub fn new(interval: u32, capacity: usize) -> Self {
+        Self {
+            interval: interval.max(1) as u64,
+            capacity,
+            snapshots: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Takes a snapshot if `interval` frames have passed since the last one. Meant
+    /// to be called after every frame, the oldest snapshot is dropped once full.
+    pub fn record(&mut self, interpreter: &Interpreter) {
+        let frame = interpreter.frames();
+        if let Some(&(last, _)) = self.snapshots.back() {
+            if frame < last + self.interval {
+                return;
+            }
+        }
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        if self.capacity > 0 {
+            self.snapshots.push_back((frame, interpreter.snapshot()));
+        }
+    }
+
+    /// Restores the latest snapshot taken before the interpreter's current frame,
+    /// dropping the ones after it. Returns false if there is none left.
+    pub fn step_back(&mut self, interpreter: &mut Interpreter) -> bool {
+        let current = interpreter.frames();
+        while let Some((frame, snapshot)) = self.snapshots.pop_back() {
+            if frame < current {
+                let restored = interpreter.restore_snapshot(&snapshot);
+                // Keep it so that playing on and stepping back again lands here
+                self.snapshots.push_back((frame, snapshot));
+                return restored;
+            }
+        }
+        false
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
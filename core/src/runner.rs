@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use tokio::{
+    sync::{mpsc, watch},
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    core::Screen,
+    error::InterpreterError,
+    interpreter::{AudioEvent, Interpreter, KeyEvent},
+};
+
+#[derive(Clone, Copy)]
+pub struct RunnerConfig {
+    pub instructions_per_frame: usize,
+    // Time between two frames, each frame ticks the timers once
+    pub frame_duration: Duration,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            instructions_per_frame: 11,
+            frame_duration: Duration::from_nanos(1_000_000_000 / 60),
+        }
+    }
+}
+
+/// Channels to a running interpreter task.
+pub struct Runner {
+    // Latest screen, only updated when it changes
+    pub frames: watch::Receiver<Screen>,
+    pub audio_events: mpsc::UnboundedReceiver<AudioEvent>,
+    // Events stamped with frame 0 apply at the next frame
+    pub input: mpsc::UnboundedSender<KeyEvent>,
+    // Completes once every frame receiver is dropped, or on the first error
+    pub task: JoinHandle<Result<(), InterpreterError>>,
+}
+
+impl Runner {
+    /// Spawns a task on the current tokio runtime that owns the interpreter
+    /// and runs it at the configured rate.
+    pub fn spawn(mut interpreter: Interpreter, config: RunnerConfig) -> Self {
+        interpreter.record_audio_events = true;
+
+        let (frames_sender, frames) = watch::channel(interpreter.screen.clone());
+        let (audio_events_sender, audio_events) = mpsc::unbounded_channel();
+        let (input, mut input_receiver) = mpsc::unbounded_channel::<KeyEvent>();
+
+        let task = tokio::spawn(async move {
+            let mut interval = time::interval(config.frame_duration);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            while !frames_sender.is_closed() {
+                interval.tick().await;
+
+                while let Ok(event) = input_receiver.try_recv() {
+                    interpreter.queue_key_event(event);
+                }
+
+                interpreter.run_frame(config.instructions_per_frame)?;
+
+                for event in interpreter.take_audio_events() {
+                    // Nobody listening for sound is fine
+                    let _ = audio_events_sender.send(event);
+                }
+                frames_sender.send_if_modified(|screen| {
+                    let changed = *screen != interpreter.screen;
+                    if changed {
+                        screen.clone_from(&interpreter.screen);
+                    }
+                    changed
+                });
+            }
+
+            Ok(())
+        });
+
+        Self {
+            frames,
+            audio_events,
+            input,
+            task,
+        }
+    }
+}
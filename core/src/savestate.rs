@@ -0,0 +1,267 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt::Debug;
+
+use crate::{
+    core::{Banks, Ram, Resolution, Screen, Timer, TimerState},
+    error::SaveStateError,
+    hash::crc32,
+    interpreter::{CloneRng, InputHandler},
+};
+
+// Layout: magic, version (u16), payload, CRC-32 of everything before it (u32).
+// Integers are little-endian.
+const MAGIC: [u8; 4] = *b"C8SS";
+// Bumped whenever the payload layout changes, older versions stay readable
+const VERSION: u16 = 1;
+
+// Machine state captured by `Interpreter::save_state`
+pub(crate) struct SaveState {
+    pub(crate) ram: Ram,
+    pub(crate) screen: Screen,
+    pub(crate) variable_registers: [u8; 16],
+    pub(crate) index_register: u16,
+    pub(crate) program_counter: u16,
+    pub(crate) stack: Vec<u16>,
+    pub(crate) delay_timer: Timer,
+    pub(crate) sound_timer: Timer,
+    pub(crate) cycles: u64,
+    pub(crate) frames: u64,
+    pub(crate) steps_since_tick: u32,
+    pub(crate) paused: bool,
+    pub(crate) halted: bool,
+    pub(crate) finished: bool,
+    // Register a pending Fx0A stores the key in
+    pub(crate) key_wait: Option<usize>,
+    pub(crate) audio_pattern: [u8; 16],
+    pub(crate) pitch: u8,
+    pub(crate) banks: Option<Banks>,
+}
+
+// Save state plus what it leaves out but undoing an instruction must bring back: the
+// random generator, so Cxkk draws the same values again, and the held keys
+pub(crate) struct Snapshot {
+    pub(crate) state: Vec<u8>,
+    pub(crate) rng: Box<dyn CloneRng>,
+    pub(crate) input_handler: InputHandler,
+}
+
+impl Clone for Snapshot {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            rng: self.rng.clone_box(),
+            input_handler: self.input_handler.clone(),
+        }
+    }
+}
+
+impl Debug for Snapshot {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("size", &self.state.len())
+            .field("input_handler", &self.input_handler)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SaveState {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+
+        let ram = self.ram.get(0..self.ram.len()).unwrap_or_default();
+        out.extend_from_slice(&(ram.len() as u32).to_le_bytes());
+        out.extend_from_slice(ram);
+
+        out.extend_from_slice(&self.variable_registers);
+        out.extend_from_slice(&self.index_register.to_le_bytes());
+        out.extend_from_slice(&self.program_counter.to_le_bytes());
+        out.extend_from_slice(&(self.stack.len() as u16).to_le_bytes());
+        for address in &self.stack {
+            out.extend_from_slice(&address.to_le_bytes());
+        }
+        for timer in [self.delay_timer, self.sound_timer] {
+            let zero = matches!(timer.state, TimerState::Zero);
+            out.extend_from_slice(&[timer.value, zero as u8]);
+        }
+
+        out.extend_from_slice(&[
+            self.screen.resolution() as u8,
+            self.screen.selected_planes(),
+        ]);
+        for row in self.screen.as_bits().iter().flatten() {
+            out.extend_from_slice(&row.to_le_bytes());
+        }
+
+        out.extend_from_slice(&self.cycles.to_le_bytes());
+        out.extend_from_slice(&self.frames.to_le_bytes());
+        out.extend_from_slice(&self.steps_since_tick.to_le_bytes());
+        let status = self.paused as u8 | (self.halted as u8) << 1 | (self.finished as u8) << 2;
+        out.push(status);
+        // 0xFF when not waiting for a key
+        out.push(self.key_wait.map_or(0xFF, |register| register as u8));
+        out.extend_from_slice(&self.audio_pattern);
+        out.push(self.pitch);
+
+        match &self.banks {
+            Some(banks) => {
+                out.push(1);
+                out.extend_from_slice(&banks.window().to_le_bytes());
+                out.extend_from_slice(&(banks.selected() as u32).to_le_bytes());
+                out.extend_from_slice(&(banks.len() as u32).to_le_bytes());
+                for bank in banks.contents() {
+                    out.extend_from_slice(&(bank.len() as u32).to_le_bytes());
+                    out.extend_from_slice(bank);
+                }
+            }
+            None => out.push(0),
+        }
+
+        let checksum = crc32(&out);
+        out.extend_from_slice(&checksum.to_le_bytes());
+        out
+    }
+
+    pub(crate) fn decode(data: &[u8]) -> Result<Self, SaveStateError> {
+        let (data, checksum) = match data.len().checked_sub(4) {
+            Some(end) if end >= MAGIC.len() && data.starts_with(&MAGIC) => data.split_at(end),
+            _ => return Err(SaveStateError::InvalidMagic),
+        };
+        if crc32(data).to_le_bytes() != checksum {
+            return Err(SaveStateError::ChecksumMismatch);
+        }
+
+        let mut reader = Reader(&data[MAGIC.len()..]);
+        match reader.u16()? {
+            1 => Self::decode_v1(&mut reader),
+            version => Err(SaveStateError::UnsupportedVersion { version }),
+        }
+    }
+
+    fn decode_v1(reader: &mut Reader) -> Result<Self, SaveStateError> {
+        let ram_size = reader.u32()? as usize;
+        let ram = Ram::from_image(reader.bytes(ram_size)?)?;
+
+        let variable_registers = reader.array()?;
+        let index_register = reader.u16()?;
+        let program_counter = reader.u16()?;
+        let depth = reader.u16()?;
+        let stack = (0..depth).map(|_| reader.u16()).collect::<Result<_, _>>()?;
+        let delay_timer = reader.timer()?;
+        let sound_timer = reader.timer()?;
+
+        // 0 and 1 for the low and high resolutions, 2 for the two-page mode
+        let resolution = match reader.u8()? {
+            0 => Resolution::Low,
+            1 => Resolution::High,
+            2 => Resolution::TwoPage,
+            _ => {
+                return Err(SaveStateError::InvalidValue {
+                    field: "resolution",
+                })
+            }
+        };
+        let selected_planes = reader.u8()?;
+        let mut planes = [[0; 64]; 2];
+        for row in planes.iter_mut().flatten() {
+            *row = u128::from_le_bytes(reader.array()?);
+        }
+        let screen = Screen::from_planes(planes, selected_planes, resolution);
+
+        let cycles = reader.u64()?;
+        let frames = reader.u64()?;
+        let steps_since_tick = reader.u32()?;
+        let status = reader.u8()?;
+        let key_wait = match reader.u8()? {
+            0xFF => None,
+            register @ 0x0..=0xF => Some(register as usize),
+            _ => return Err(SaveStateError::InvalidValue { field: "key wait" }),
+        };
+        let audio_pattern = reader.array()?;
+        let pitch = reader.u8()?;
+
+        let banks = match reader.u8()? {
+            0 => None,
+            _ => {
+                let window = reader.u16()?;
+                let selected = reader.u32()? as usize;
+                let count = reader.u32()?;
+                let contents = (0..count)
+                    .map(|_| {
+                        let size = reader.u32()? as usize;
+                        Ok(reader.bytes(size)?.to_vec())
+                    })
+                    .collect::<Result<_, SaveStateError>>()?;
+                let banks = Banks::new(window, contents)?.with_selected(selected)?;
+                banks.check_window(&ram)?;
+                Some(banks)
+            }
+        };
+
+        Ok(Self {
+            ram,
+            screen,
+            variable_registers,
+            index_register,
+            program_counter,
+            stack,
+            delay_timer,
+            sound_timer,
+            cycles,
+            frames,
+            steps_since_tick,
+            paused: status & 0b001 != 0,
+            halted: status & 0b010 != 0,
+            finished: status & 0b100 != 0,
+            key_wait,
+            audio_pattern,
+            pitch,
+            banks,
+        })
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], SaveStateError> {
+        if self.0.len() < count {
+            return Err(SaveStateError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SaveStateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SaveStateError> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, SaveStateError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, SaveStateError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn timer(&mut self) -> Result<Timer, SaveStateError> {
+        let value = self.u8()?;
+        let state = match self.u8()? {
+            0 => TimerState::AboveZero,
+            _ => TimerState::Zero,
+        };
+        Ok(Timer { value, state })
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(feature = "std")]
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use alloc::{string::ToString, vec::Vec};
+use core::str::FromStr;
+
+#[cfg(feature = "std")]
+use crate::hash::crc32;
+use crate::{core::Ram, error::ScoreLocationError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreEncoding {
+    // One decimal digit per byte, most significant first (as written by Fx33)
+    UnpackedBcd,
+    // Two decimal digits per byte, most significant first
+    PackedBcd,
+    // Big-endian unsigned integer
+    Binary,
+}
+
+/// Describes where and how a game keeps its score in memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScoreLocation {
+    pub address: u16,
+    pub length: u16,
+    pub encoding: ScoreEncoding,
+}
+
+impl ScoreLocation {
+    /// Decodes the score, or returns `None` if the location is out of bounds or
+    /// holds something that isn't a valid score in this encoding.
+    pub fn read(&self, ram: &Ram) -> Option<u32> {
+        let start = self.address as usize;
+        let bytes = ram.get(start..start + self.length as usize)?;
+
+        bytes
+            .iter()
+            .try_fold(0u32, |score, &byte| match self.encoding {
+                ScoreEncoding::UnpackedBcd if byte <= 9 => {
+                    score.checked_mul(10)?.checked_add(byte as u32)
+                }
+                ScoreEncoding::PackedBcd if byte >> 4 <= 9 && byte & 0x0F <= 9 => score
+                    .checked_mul(100)?
+                    .checked_add((byte >> 4) as u32 * 10 + (byte & 0x0F) as u32),
+                ScoreEncoding::Binary => score.checked_mul(256)?.checked_add(byte as u32),
+                _ => None,
+            })
+    }
+}
+
+/// Parses `<address>:<length>:<encoding>`, the address in hexadecimal and the
+/// encoding one of `bcd`, `packed` or `binary`, e.g. `0x2F0:3:bcd`.
+impl FromStr for ScoreLocation {
+    type Err = ScoreLocationError;
+
+    fn from_str(location: &str) -> Result<Self, Self::Err> {
+        let mut fields = location.split(':');
+        let (Some(address), Some(length), Some(encoding), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(ScoreLocationError::InvalidFormat);
+        };
+
+        let address = address.strip_prefix("0x").unwrap_or(address);
+        let address =
+            u16::from_str_radix(address, 16).map_err(|_| ScoreLocationError::InvalidFormat)?;
+        let length = match length.parse() {
+            Ok(length @ 1..) => length,
+            _ => return Err(ScoreLocationError::InvalidFormat),
+        };
+        let encoding = match encoding {
+            "bcd" => ScoreEncoding::UnpackedBcd,
+            "packed" => ScoreEncoding::PackedBcd,
+            "binary" => ScoreEncoding::Binary,
+            _ => return Err(ScoreLocationError::UnknownEncoding(encoding.to_string())),
+        };
+
+        Ok(Self {
+            address,
+            length,
+            encoding,
+        })
+    }
+}
+
+/// The best scores for a game, highest first.
+pub struct HighScores {
+    scores: Vec<u32>,
+    capacity: usize,
+}
+
+impl HighScores {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            scores: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Restores a table, e.g. one previously persisted by a frontend.
+    pub fn from_scores(mut scores: Vec<u32>, capacity: usize) -> Self {
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.truncate(capacity);
+        Self { scores, capacity }
+    }
+
+    pub fn scores(&self) -> &[u32] {
+        &self.scores
+    }
+
+    /// Records a finished session's score, returning its rank (0 being the
+    /// best) if it made it into the table.
+    pub fn submit(&mut self, score: u32) -> Option<usize> {
+        // Ties rank below existing scores
+        let rank = self.scores.partition_point(|&existing| existing >= score);
+        if rank >= self.capacity {
+            return None;
+        }
+
+        self.scores.insert(rank, score);
+        self.scores.truncate(self.capacity);
+        Some(rank)
+    }
+}
+
+#[cfg(feature = "std")]
+impl HighScores {
+    /// Where a program's table lives in `directory`. Tables are named after the
+    /// program's CRC-32, so a ROM keeps its scores whatever its file is called.
+    pub fn path(directory: &Path, program: &[u8]) -> PathBuf {
+        directory.join(format!("{:08x}.scores", crc32(program)))
+    }
+
+    /// Reads a table written by [`HighScores::save`]. A missing or unreadable
+    /// file gives an empty table, and lines that aren't scores are skipped.
+    pub fn load(path: &Path, capacity: usize) -> Self {
+        let scores = fs::read_to_string(path).unwrap_or_default();
+        let scores = scores
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect();
+        Self::from_scores(scores, capacity)
+    }
+
+    /// Writes the table, one score per line.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let lines: Vec<_> = self
+            .scores
+            .iter()
+            .map(|score| format!("{score}\n"))
+            .collect();
+        fs::write(path, lines.concat())
+    }
+}
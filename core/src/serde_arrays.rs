@@ -0,0 +1,54 @@
+use alloc::vec::Vec;
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+// serde only implements its traits for arrays of up to 32 elements, larger ones
+// go through these as sequences
+
+pub(crate) fn serialize<S: Serializer, T: Serialize, const N: usize>(
+    array: &[T; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(array)
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[T; N], D::Error> {
+    let items = Vec::<T>::deserialize(deserializer)?;
+    let len = items.len();
+    items
+        .try_into()
+        .map_err(|_| D::Error::invalid_length(len, &"a fixed size array"))
+}
+
+// Arrays of arrays, flattened into a single sequence
+pub(crate) mod nested {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer, T: Serialize, const M: usize, const N: usize>(
+        array: &[[T; N]; M],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(array.iter().flatten())
+    }
+
+    pub(crate) fn deserialize<'de, D, T, const M: usize, const N: usize>(
+        deserializer: D,
+    ) -> Result<[[T; N]; M], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + Copy + Default,
+    {
+        let items = Vec::<T>::deserialize(deserializer)?;
+        if items.len() != M * N {
+            return Err(D::Error::invalid_length(items.len(), &"a fixed size array"));
+        }
+
+        let mut array = [[T::default(); N]; M];
+        for (row, chunk) in array.iter_mut().zip(items.chunks_exact(N)) {
+            row.copy_from_slice(chunk);
+        }
+        Ok(array)
+    }
+}
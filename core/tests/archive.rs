@@ -0,0 +1,112 @@
+#![cfg(feature = "archive")]
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chippers_core::archive::read_roms;
+use flate2::{write::GzEncoder, Compression};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+// A scratch directory per test, so tests can run in parallel
+fn scratch_directory(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("chippers_archive_{test}"));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+    let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+    for (name, data) in entries {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn plain_rom() {
+    let path = scratch_directory("plain").join("game.ch8");
+    fs::write(&path, [0x12, 0x00]).unwrap();
+
+    let roms = read_roms(&path).unwrap();
+    assert_eq!(roms.len(), 1);
+    assert_eq!(roms[0].name, path.display().to_string());
+    assert_eq!(roms[0].data, [0x12, 0x00]);
+}
+
+#[test]
+fn zip_yields_every_rom() {
+    let path = scratch_directory("zip").join("pack.zip");
+    write_zip(
+        &path,
+        &[
+            ("first.ch8", &[0x60, 0x01]),
+            ("README.txt", b"not a ROM"),
+            ("games/SECOND.CH8", &[0x60, 0x02]),
+        ],
+    );
+
+    let roms = read_roms(&path).unwrap();
+    let names: Vec<&str> = roms.iter().map(|rom| rom.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            format!("{}/first.ch8", path.display()),
+            format!("{}/games/SECOND.CH8", path.display()),
+        ]
+    );
+    assert_eq!(roms[1].data, [0x60, 0x02]);
+}
+
+#[test]
+fn gzip_is_decompressed() {
+    let path = scratch_directory("gzip").join("game.ch8.gz");
+    let mut encoder = GzEncoder::new(fs::File::create(&path).unwrap(), Compression::default());
+    encoder.write_all(&[0x61, 0x23]).unwrap();
+    encoder.finish().unwrap();
+
+    let roms = read_roms(&path).unwrap();
+    assert_eq!(roms.len(), 1);
+    assert_eq!(
+        roms[0].name,
+        path.display().to_string().trim_end_matches(".gz")
+    );
+    assert_eq!(roms[0].data, [0x61, 0x23]);
+}
+
+#[test]
+fn extensions_are_case_insensitive() {
+    let directory = scratch_directory("case");
+    let zip = directory.join("PACK.ZIP");
+    write_zip(&zip, &[("GAME.CH8", &[0x60, 0x01])]);
+    let gzip = directory.join("GAME.CH8.GZ");
+    let mut encoder = GzEncoder::new(fs::File::create(&gzip).unwrap(), Compression::default());
+    encoder.write_all(&[0x61, 0x23]).unwrap();
+    encoder.finish().unwrap();
+
+    let roms = read_roms(&zip).unwrap();
+    assert_eq!(roms[0].name, format!("{}/GAME.CH8", zip.display()));
+    assert_eq!(roms[0].data, [0x60, 0x01]);
+
+    let roms = read_roms(&gzip).unwrap();
+    assert_eq!(
+        roms[0].name,
+        directory.join("GAME.CH8").display().to_string()
+    );
+    assert_eq!(roms[0].data, [0x61, 0x23]);
+}
+
+#[test]
+fn corrupt_archives_fail() {
+    let directory = scratch_directory("corrupt");
+    for name in ["broken.zip", "broken.gz"] {
+        let path = directory.join(name);
+        fs::write(&path, b"definitely not compressed").unwrap();
+        assert!(read_roms(&path).is_err(), "{name}");
+    }
+    assert!(read_roms(&directory.join("missing.ch8")).is_err());
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use chippers_core::{
+    audio::{AudioGenerator, AudioSink},
+    interpreter::{AudioEvent, AudioEventKind, Interpreter},
+};
+
+use common::given;
+
+#[test]
+fn audio_generator_plays_pattern() {
+    let mut state = given();
+    let mut generator = AudioGenerator::new(8000);
+    generator.volume = 0.5;
+
+    let mut buffer = [1.0; 8];
+    generator.fill(&state.interpreter, &mut buffer);
+    assert_eq!(buffer, [0.0; 8]);
+
+    // The default pattern at 4000 bits per second is a 500 Hz square wave
+    state.interpreter.sound_timer.value = 10;
+    generator.fill(&state.interpreter, &mut buffer);
+    assert_eq!(buffer, [0.5; 8]);
+    generator.fill(&state.interpreter, &mut buffer);
+    assert_eq!(buffer, [-0.5; 8]);
+}
+
+#[derive(Clone, Default)]
+struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+impl AudioSink for RecordingSink {
+    fn beep_on(&mut self) {
+        self.0.lock().unwrap().push("on".into());
+    }
+
+    fn beep_off(&mut self) {
+        self.0.lock().unwrap().push("off".into());
+    }
+
+    fn queue_samples(&mut self, samples: &[f32]) {
+        let loud = samples.iter().filter(|&&sample| sample != 0.0).count();
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{loud}/{}", samples.len()));
+    }
+}
+
+#[test]
+fn audio_sink_follows_sound_timer() {
+    // Plays sound for two ticks, then counts in V1
+    let program = [0x60, 0x02, 0xF0, 0x18, 0x71, 0x01, 0x12, 0x04];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    let sink = RecordingSink::default();
+    interpreter.set_audio_sink(sink.clone(), 6000);
+
+    for _ in 0..3 {
+        interpreter.run_frame(4).unwrap();
+    }
+    assert_eq!(
+        *sink.0.lock().unwrap(),
+        ["on", "100/100", "100/100", "off", "0/100"]
+    );
+}
+
+#[test]
+fn audio_sink_with_zero_timer_rate() {
+    let mut interpreter = Interpreter::new(&[0x60, 0x02, 0xF0, 0x18]).unwrap();
+    let sink = RecordingSink::default();
+    interpreter.set_audio_sink(sink.clone(), 100);
+    interpreter.clock.timer_hz = 0;
+
+    interpreter.run_frame(2).unwrap();
+    assert_eq!(*sink.0.lock().unwrap(), ["on", "100/100"]);
+}
+
+#[test]
+fn beep_events_are_stamped() {
+    // Beeps for two ticks, then counts in V1
+    let program = [0x60, 0x02, 0xF0, 0x18, 0x71, 0x01, 0x12, 0x04];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.record_audio_events = true;
+    for _ in 0..4 {
+        interpreter.run_frame(4).unwrap();
+    }
+
+    assert_eq!(
+        interpreter.take_audio_events(),
+        [
+            // Fx18 is the second instruction
+            AudioEvent {
+                kind: AudioEventKind::BeepStart,
+                cycle: 1,
+                frame: 0
+            },
+            // Stopped by the second tick
+            AudioEvent {
+                kind: AudioEventKind::BeepStop,
+                cycle: 8,
+                frame: 2
+            },
+        ]
+    );
+    assert!(interpreter.take_audio_events().is_empty());
+
+    // Nothing is recorded unless asked for
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.run_frame(4).unwrap();
+    assert!(interpreter.take_audio_events().is_empty());
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn pattern_and_pitch_events() {
+    let pattern = [0xAA; 16];
+    let mut state = given().mem(0x300, &pattern).i(0x300).reg(1, 100);
+    state.interpreter.record_audio_events = true;
+
+    // Loading the same pattern or pitch again isn't a change
+    let mut state = state.exec(0xF002).exec(0xF002).exec(0xF13A).exec(0xF13A);
+    let events: Vec<AudioEventKind> = state
+        .interpreter
+        .take_audio_events()
+        .into_iter()
+        .map(|event| event.kind)
+        .collect();
+    assert_eq!(
+        events,
+        [
+            AudioEventKind::PatternChanged { pattern },
+            AudioEventKind::PitchChanged { pitch: 100 },
+        ]
+    );
+}
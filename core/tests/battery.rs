@@ -0,0 +1,91 @@
+mod common;
+
+use chippers_core::{
+    battery::BatteryRange,
+    error::{BatteryError, LoadError},
+    interpreter::Interpreter,
+};
+
+use common::IDLE;
+
+#[test]
+fn parse_battery_range() {
+    assert_eq!(
+        "0x2F0:16".parse::<BatteryRange>().unwrap(),
+        BatteryRange {
+            address: 0x2F0,
+            length: 16
+        }
+    );
+    assert_eq!(
+        "e00:512".parse::<BatteryRange>().unwrap(),
+        BatteryRange {
+            address: 0xE00,
+            length: 512
+        }
+    );
+
+    for invalid in ["", "0x2F0", "0x2F0:0", "0xZZZ:16", "0x2F0:16:bcd"] {
+        assert!(
+            matches!(
+                invalid.parse::<BatteryRange>(),
+                Err(BatteryError::InvalidFormat)
+            ),
+            "{invalid}"
+        );
+    }
+}
+
+#[test]
+fn read_and_restore() {
+    let range: BatteryRange = "0x300:3".parse().unwrap();
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    range.restore(&mut interpreter, &[1, 2, 3]).unwrap();
+    assert_eq!(range.read(&interpreter.ram), Some(&[1, 2, 3][..]));
+
+    assert!(matches!(
+        range.restore(&mut interpreter, &[1, 2]),
+        Err(BatteryError::SizeMismatch {
+            size: 2,
+            expected: 3
+        })
+    ));
+
+    let past_the_end: BatteryRange = "0xFFF:2".parse().unwrap();
+    assert_eq!(past_the_end.read(&interpreter.ram), None);
+    assert!(matches!(
+        past_the_end.restore(&mut interpreter, &[1, 2]),
+        Err(BatteryError::Load(LoadError::SegmentOutOfBounds {
+            address: 0xFFF,
+            size: 2
+        }))
+    ));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn save_and_load() {
+    use std::fs;
+
+    let directory = std::env::temp_dir().join("chippers_battery");
+    fs::create_dir_all(&directory).unwrap();
+    let path = BatteryRange::path(&directory, &[0x12, 0x00]);
+    assert_eq!(path, directory.join("392d622c.sav"));
+    let _ = fs::remove_file(&path);
+
+    let range: BatteryRange = "0x300:2".parse().unwrap();
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    // Nothing is restored before the first save
+    assert!(!range.load(&path, &mut interpreter).unwrap());
+
+    range.restore(&mut interpreter, &[0xAB, 0xCD]).unwrap();
+    range.save(&path, &interpreter.ram).unwrap();
+
+    let mut next_session = Interpreter::new(&IDLE).unwrap();
+    assert!(range.load(&path, &mut next_session).unwrap());
+    assert_eq!(range.read(&next_session.ram), Some(&[0xAB, 0xCD][..]));
+
+    // A save made for another range isn't applied
+    let other: BatteryRange = "0x300:4".parse().unwrap();
+    assert!(other.load(&path, &mut next_session).is_err());
+}
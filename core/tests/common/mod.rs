@@ -0,0 +1,146 @@
+// Shared by every test crate, each of which only uses part of it
+#![allow(dead_code)]
+
+use chippers_core::error::InterpreterError;
+use chippers_core::interpreter::{Interpreter, Key, Quirks};
+
+// Jumps to itself
+pub const IDLE: [u8; 2] = [0x12, 0x00];
+
+// Hash of a blank low resolution screen
+pub const SCREEN_HASH: u64 = 0x9954_C7D5_9782_AE3A;
+
+// Builds a machine state, runs opcodes on it and checks the resulting state:
+// `given().reg(3, 0x1F).exec(0x8344).expect_reg(3, 0x3E)`
+pub struct State {
+    pub interpreter: Interpreter,
+}
+
+pub fn given() -> State {
+    State {
+        interpreter: Interpreter::new(&IDLE).unwrap(),
+    }
+}
+
+impl State {
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.interpreter.quirks = quirks;
+        self
+    }
+
+    pub fn reg(mut self, register: usize, value: u8) -> Self {
+        self.interpreter.variable_registers[register] = value;
+        self
+    }
+
+    pub fn i(mut self, address: u16) -> Self {
+        self.interpreter.index_register = address;
+        self
+    }
+
+    pub fn pc(mut self, address: u16) -> Self {
+        self.interpreter.program_counter = address;
+        self
+    }
+
+    pub fn mem(mut self, address: u16, bytes: &[u8]) -> Self {
+        for (offset, &byte) in bytes.iter().enumerate() {
+            self.interpreter.ram[address + offset as u16] = byte;
+        }
+        self
+    }
+
+    pub fn dt(mut self, value: u8) -> Self {
+        self.interpreter.delay_timer.value = value;
+        self
+    }
+
+    pub fn key(mut self, key: usize) -> Self {
+        self.interpreter.input_handler.press(Key::from(key as u8));
+        self
+    }
+
+    // Writes the opcode at the program counter and executes it
+    #[track_caller]
+    pub fn exec(self, opcode: u16) -> Self {
+        match self.try_exec(opcode) {
+            Ok(state) => state,
+            Err(error) => panic!("{opcode:04X} failed: {error}"),
+        }
+    }
+
+    pub fn try_exec(mut self, opcode: u16) -> Result<Self, InterpreterError> {
+        let pc = self.interpreter.program_counter;
+        self = self.mem(pc, &opcode.to_be_bytes());
+        self.interpreter.step()?;
+        Ok(self)
+    }
+
+    #[track_caller]
+    pub fn expect_reg(self, register: usize, value: u8) -> Self {
+        assert_eq!(
+            self.interpreter.variable_registers[register], value,
+            "V{register:X}"
+        );
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_vf(self, value: u8) -> Self {
+        self.expect_reg(0xF, value)
+    }
+
+    #[track_caller]
+    pub fn expect_i(self, address: u16) -> Self {
+        assert_eq!(self.interpreter.index_register, address, "I");
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_pc(self, address: u16) -> Self {
+        assert_eq!(self.interpreter.program_counter, address, "PC");
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_mem(self, address: u16, bytes: &[u8]) -> Self {
+        let start = address as usize;
+        assert_eq!(
+            &self.interpreter.ram[start..start + bytes.len()],
+            bytes,
+            "memory at {address:#05X}"
+        );
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_stack(self, addresses: &[u16]) -> Self {
+        let stack: Vec<u16> = self.interpreter.stack.iter().collect();
+        assert_eq!(stack, addresses, "stack");
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_dt(self, value: u8) -> Self {
+        assert_eq!(self.interpreter.delay_timer.value, value, "DT");
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_st(self, value: u8) -> Self {
+        assert_eq!(self.interpreter.sound_timer.value, value, "ST");
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_pixels(self, y: usize, row: &str) -> Self {
+        let pixels: String = (0..row.len())
+            .map(|x| match self.interpreter.screen.get(x, y) {
+                true => '#',
+                false => '.',
+            })
+            .collect();
+        assert_eq!(pixels, row, "row {y}");
+        self
+    }
+}
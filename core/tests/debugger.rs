@@ -0,0 +1,199 @@
+#![cfg(feature = "debug")]
+
+use chippers_core::{
+    debug::{Comparison, Condition, Operand},
+    error::ConditionError,
+    instructions::Instruction,
+    interpreter::{Interpreter, Key, Status},
+};
+
+#[test]
+fn breakpoints_stop_execution() {
+    // Counts in V0 forever
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    interpreter.debugger.add_breakpoint(0x202);
+
+    let frame = interpreter.run_frame(10).unwrap();
+    assert_eq!(frame.cycles, 1);
+    assert!(matches!(interpreter.status(), Status::BreakpointHit(0x202)));
+    assert_eq!(interpreter.frames(), 0);
+
+    // Stepping again runs the instruction at the breakpoint
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.program_counter, 0x200);
+    assert!(matches!(interpreter.status(), Status::Running));
+
+    assert!(interpreter.debugger.remove_breakpoint(0x202));
+    assert_eq!(interpreter.run_frame(10).unwrap().cycles, 10);
+}
+
+#[test]
+fn conditional_breakpoints() {
+    let condition: Condition = "V3 == 0x1F".parse().unwrap();
+    assert_eq!(condition.left, Operand::Register(3));
+    assert_eq!(condition.comparison, Comparison::Equal);
+    assert_eq!(condition.right, Operand::Value(0x1F));
+    assert_eq!(
+        "i>=768".parse::<Condition>().unwrap(),
+        Condition {
+            left: Operand::Index,
+            comparison: Comparison::GreaterOrEqual,
+            right: Operand::Value(768),
+        }
+    );
+    assert_eq!(
+        "V3".parse::<Condition>(),
+        Err(ConditionError::MissingComparison)
+    );
+    assert_eq!(
+        "VG < 2".parse::<Condition>(),
+        Err(ConditionError::UnknownOperand("VG".into()))
+    );
+
+    // Counts in V0 forever
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    interpreter
+        .debugger
+        .add_conditional_breakpoint(0x202, "V0 == 3".parse().unwrap());
+    interpreter.run_frame(20).unwrap();
+    assert!(matches!(interpreter.status(), Status::BreakpointHit(0x202)));
+    assert_eq!(interpreter.variable_registers[0], 3);
+
+    interpreter.debugger.clear_breakpoints();
+    interpreter.debugger.add_watch("V0 >= 5".parse().unwrap());
+    interpreter.run_frame(20).unwrap();
+    assert!(matches!(interpreter.status(), Status::BreakpointHit(0x202)));
+    assert_eq!(interpreter.variable_registers[0], 5);
+}
+
+#[test]
+fn step_back_undoes_instructions() {
+    // Counts in V0 forever
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    assert!(!interpreter.step_back());
+    interpreter.debugger.set_history_capacity(3);
+
+    for _ in 0..6 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(interpreter.variable_registers[0], 3);
+    assert_eq!(interpreter.debugger.history_len(), 3);
+
+    assert!(interpreter.step_back());
+    assert_eq!(interpreter.program_counter, 0x202);
+    assert!(interpreter.step_back());
+    assert_eq!(interpreter.program_counter, 0x200);
+    assert_eq!(interpreter.variable_registers[0], 2);
+    assert!(interpreter.step_back());
+    assert!(!interpreter.step_back());
+    assert_eq!(interpreter.cycles(), 3);
+}
+
+#[test]
+fn step_back_restores_random_generator_and_keys() {
+    // Draws a random byte into V0 forever
+    let mut interpreter = Interpreter::builder()
+        .deterministic(7, 10)
+        .build(&[0xC0, 0xFF, 0x12, 0x00])
+        .unwrap();
+    interpreter.debugger.set_history_capacity(8);
+    interpreter.input_handler.press(Key::from(5));
+
+    let mut values = Vec::new();
+    for _ in 0..4 {
+        interpreter.step().unwrap();
+        interpreter.step().unwrap();
+        values.push(interpreter.variable_registers[0]);
+    }
+
+    for _ in 0..4 {
+        assert!(interpreter.step_back());
+    }
+    assert!(interpreter.input_handler.is_pressed(Key::from(5)));
+    for &value in &values[2..] {
+        interpreter.step().unwrap();
+        interpreter.step().unwrap();
+        assert_eq!(interpreter.variable_registers[0], value);
+    }
+}
+
+#[test]
+fn debug_view() {
+    let program = [
+        0x60, 0x07, // LD V0, 0x07
+        0x22, 0x08, // CALL 0x208
+        0x50, 0x01, // invalid
+        0x12, 0x06, // JP 0x206
+        0xF0, 0x15, // LD DT, V0
+    ];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+
+    let view = interpreter.debug_view();
+    assert_eq!(view.program_counter, 0x20A);
+    assert_eq!(view.registers[0], 7);
+    assert_eq!(view.delay_timer, 7);
+    assert_eq!(view.stack, [0x204]);
+    assert_eq!(view.cycles, 3);
+    assert_eq!(view.waiting_for_key, None);
+    assert!(!view.paused);
+
+    // Decoding goes on linearly from the program counter, up to the end of memory
+    assert_eq!(view.next_instructions.len(), 8);
+    assert_eq!(view.next_instructions[0].address, 0x20A);
+    assert_eq!(view.next_instructions[0].opcode, 0x0000);
+
+    interpreter.program_counter = 0x204;
+    let view = interpreter.debug_view();
+    assert_eq!(view.next_instructions[0].instruction, None);
+    assert_eq!(
+        view.next_instructions[1].instruction,
+        Some(Instruction::Jump { address: 0x206 })
+    );
+
+    interpreter.program_counter = 0xFFC;
+    let view = interpreter.debug_view();
+    let addresses: Vec<u16> = view
+        .next_instructions
+        .iter()
+        .map(|line| line.address)
+        .collect();
+    assert_eq!(addresses, [0xFFC, 0xFFE]);
+}
+
+#[test]
+fn debug_view_watches_and_dirty_regions() {
+    let program = [
+        0xA2, 0x0A, // LD I, 0x20A
+        0x60, 0x05, // LD V0, 0x05
+        0x61, 0x04, // LD V1, 0x04
+        0xD0, 0x12, // DRW V0, V1, 2
+        0x12, 0x08, // JP 0x208
+        0xFF, 0x81, // sprite
+    ];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.debugger.add_watch("V0 == 7".parse().unwrap());
+
+    // The first view has nothing to compare the screen with
+    let view = interpreter.debug_view();
+    assert_eq!(view.dirty_regions.len(), 1);
+    assert_eq!(view.dirty_regions[0], 0..32);
+    assert_eq!(view.watches.len(), 1);
+    assert_eq!((view.watches[0].left, view.watches[0].right), (0, 7));
+    assert!(!view.watches[0].holds);
+
+    for _ in 0..3 {
+        interpreter.step().unwrap();
+    }
+    let view = interpreter.debug_view();
+    assert!(view.dirty_regions.is_empty());
+    assert_eq!(view.watches[0].left, 5);
+
+    interpreter.step().unwrap();
+    let view = interpreter.debug_view();
+    assert_eq!(view.dirty_regions.len(), 1);
+    assert_eq!(view.dirty_regions[0], 4..6);
+    assert!(interpreter.debug_view().dirty_regions.is_empty());
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "debug")]
+
+use chippers_core::{decompiler::decompile, disassembler::analyze};
+
+#[test]
+fn loops_and_branches() {
+    let program = [
+        0x60, 0x00, // LD V0, 0
+        0x22, 0x0E, // CALL 0x20E
+        0x40, 0x05, // SNE V0, 5
+        0x12, 0x0C, // JP 0x20C
+        0x70, 0x01, // ADD V0, 1
+        0x12, 0x04, // JP 0x204
+        0x12, 0x0C, // JP 0x20C
+        0x31, 0x00, // SE V1, 0
+        0x12, 0x16, // JP 0x216
+        0x61, 0x01, // LD V1, 1
+        0x12, 0x18, // JP 0x218
+        0x61, 0x02, // LD V1, 2
+        0xD1, 0x25, // DRW V1, V2, 5
+        0xE1, 0x9E, // SKP V1
+        0x00, 0xE0, // CLS
+        0x00, 0xEE, // RET
+        0xFF, 0x81, // sprite data
+    ];
+    let expected = "\
+: main
+  # reads v0, writes v0
+  v0 := 0
+  sub_20E
+  loop
+    if v0 == 5 then jump label_20C
+    v0 += 1
+  again
+  : label_20C
+  loop
+  again
+
+: sub_20E
+  # reads v1 v2, writes v1 vf
+  if v1 == 0 begin
+    v1 := 1
+  else
+    v1 := 2
+  end
+  sprite v1 v2 5
+  if v1 -key then clear
+  return
+";
+    assert_eq!(decompile(&analyze(&program, 0x200)), expected);
+}
+
+#[test]
+fn if_without_else_and_unstructured_jumps() {
+    let program = [
+        0xE0, 0xA1, // SKNP V0
+        0x12, 0x08, // JP 0x208
+        0xA2, 0x10, // LD I, 0x210
+        0xF0, 0x33, // LD B, V0
+        0x30, 0x01, // SE V0, 1
+        0x12, 0x00, // JP 0x200
+        0x00, 0xEE, // RET
+    ];
+    // The guarded jump back isn't a loop, so `main` keeps being jumped to by name
+    let expected = "\
+: main
+  # reads v0
+  if v0 -key begin
+    i := 0x210
+    bcd v0
+  end
+  if v0 != 1 then jump main
+  return
+";
+    assert_eq!(decompile(&analyze(&program, 0x200)), expected);
+}
+
+#[test]
+fn empty_program() {
+    assert_eq!(decompile(&analyze(&[], 0x200)), "");
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "debug")]
+
+use chippers_core::{
+    disassembler::{analyze, disassemble, ReferenceKind, Xref},
+    instructions::Instruction,
+};
+
+#[test]
+fn disassembly() {
+    let program = [
+        0x63, 0x20, // LD V3, 0x20
+        0xD1, 0x25, // DRW V1, V2, 5
+        0x12, 0x00, // JP 0x200
+        0xF0, 0x00, 0x12, 0x34, // LD I, 0x1234
+        0x50, 0x01, // invalid
+        0xAB, // trailing byte
+    ];
+    let lines = disassemble(&program, 0x200);
+
+    let addresses: Vec<u16> = lines.iter().map(|line| line.address).collect();
+    assert_eq!(addresses, [0x200, 0x202, 0x204, 0x206, 0x20A, 0x20C]);
+    assert_eq!(
+        lines[0].instruction,
+        Some(Instruction::SetWithByte {
+            register: 3,
+            byte: 0x20
+        })
+    );
+    assert_eq!(lines[3].long_address, Some(0x1234));
+    assert_eq!(lines[4].instruction, None);
+
+    let text: Vec<String> = lines.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        text,
+        [
+            "0x200  6320  LD V3, 0x20",
+            "0x202  D125  DRW V1, V2, 5",
+            "0x204  1200  JP 0x200",
+            "0x206  F000  LD I, 0x1234",
+            "0x20A  5001  DW 0x5001",
+            "0x20C  AB    DB 0xAB",
+        ]
+    );
+}
+
+#[test]
+fn disassembly_analysis() {
+    let program = [
+        0x22, 0x08, // 0x200: CALL sub_208
+        0x30, 0x01, // 0x202: SE V0, 0x01
+        0x12, 0x02, // 0x204: JP label_202
+        0x00, 0xFD, // 0x206: EXIT
+        0xA2, 0x0E, // 0x208: LD I, 0x20E
+        0xD0, 0x11, // 0x20A: DRW V0, V1, 1
+        0x00, 0xEE, // 0x20C: RET
+        0xFF, // 0x20E: sprite
+    ];
+    let analysis = analyze(&program, 0x200);
+
+    for address in [0x200, 0x202, 0x204, 0x206, 0x208, 0x20A, 0x20C] {
+        assert!(analysis.is_code(address), "{address:#05X}");
+    }
+    // Only referenced by the index load, so it stays data
+    assert!(!analysis.is_code(0x20E));
+    assert_eq!(analysis.lines.last().unwrap().opcode, 0xFF);
+
+    assert_eq!(analysis.labels[&0x208], "sub_208");
+    assert_eq!(analysis.labels[&0x202], "label_202");
+    assert!(!analysis.labels.contains_key(&0x20E));
+    assert_eq!(
+        analysis.xrefs[&0x20E],
+        [Xref {
+            from: 0x208,
+            kind: ReferenceKind::Index
+        }]
+    );
+
+    let listing = analysis.to_string();
+    assert!(listing.contains("sub_208:  ; from 0x200\n0x208  A20E  LD I, 0x20E\n"));
+    assert!(listing.contains("0x204  1202  JP 0x202  ; label_202\n"));
+    assert!(listing.ends_with("0x20E  FF    DB 0xFF\n"));
+}
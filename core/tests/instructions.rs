@@ -0,0 +1,756 @@
+mod common;
+
+use chippers_core::{
+    error::InterpreterError,
+    interpreter::{Interpreter, Key, KeyWait, Quirks},
+};
+use rand::rngs::mock::StepRng;
+
+use common::{given, State};
+
+// Subroutines
+
+#[test]
+fn call_pushes_return_address() {
+    given().exec(0x2456).expect_pc(0x456).expect_stack(&[0x202]);
+    given()
+        .pc(0x400)
+        .exec(0x2456)
+        .exec(0x2600)
+        .expect_stack(&[0x402, 0x458]);
+}
+
+#[test]
+fn return_pops_return_address() {
+    given()
+        .exec(0x2456)
+        .exec(0x00EE)
+        .expect_pc(0x202)
+        .expect_stack(&[]);
+}
+
+#[test]
+fn return_with_empty_stack_fails() {
+    let result = given().try_exec(0x00EE);
+    assert!(matches!(
+        result,
+        Err(InterpreterError::StackUnderflow { address: 0x200 })
+    ));
+}
+
+#[test]
+fn call_with_full_stack_fails() {
+    let mut state = given();
+    for _ in 0..16 {
+        state = state.exec(0x2200);
+    }
+    assert!(matches!(
+        state.try_exec(0x2200),
+        Err(InterpreterError::StackOverflow { address: 0x200 })
+    ));
+}
+
+#[test]
+fn stack_errors_at_end_of_memory() {
+    let at_end_of_memory = |opcode: &[u8]| State {
+        interpreter: Interpreter::builder()
+            .memory_size(0x10000)
+            .build(opcode)
+            .unwrap(),
+    };
+
+    // The program counter has wrapped to 0 when the error is reported
+    let mut state = at_end_of_memory(&[0x00, 0xEE])
+        .pc(0xFFFE)
+        .mem(0xFFFE, &[0x00, 0xEE]);
+    assert!(matches!(
+        state.interpreter.step(),
+        Err(InterpreterError::StackUnderflow { address: 0xFFFE })
+    ));
+
+    let mut state = at_end_of_memory(&[0x22, 0x00])
+        .pc(0xFFFE)
+        .mem(0xFFFE, &[0x22, 0x00]);
+    state.interpreter.max_stack_depth = 0;
+    assert!(matches!(
+        state.interpreter.step(),
+        Err(InterpreterError::StackOverflow { address: 0xFFFE })
+    ));
+}
+
+// Control flow
+
+#[test]
+fn jump() {
+    given().exec(0x1ABC).expect_pc(0xABC);
+}
+
+#[test]
+fn jump_offset_uses_v0() {
+    given()
+        .reg(0, 0x10)
+        .reg(3, 0x20)
+        .exec(0xB300)
+        .expect_pc(0x310);
+}
+
+#[test]
+fn jump_offset_uses_vx() {
+    given()
+        .quirks(Quirks::MODERN)
+        .reg(0, 0x10)
+        .reg(3, 0x20)
+        .exec(0xB300)
+        .expect_pc(0x320);
+}
+
+#[test]
+fn skip_equal_byte() {
+    given().reg(1, 0x42).exec(0x3142).expect_pc(0x204);
+    given().reg(1, 0x41).exec(0x3142).expect_pc(0x202);
+}
+
+#[test]
+fn skip_not_equal_byte() {
+    given().reg(1, 0x42).exec(0x4142).expect_pc(0x202);
+    given().reg(1, 0x41).exec(0x4142).expect_pc(0x204);
+}
+
+#[test]
+fn skip_equal_variable() {
+    given().reg(1, 7).reg(2, 7).exec(0x5120).expect_pc(0x204);
+    given().reg(1, 7).reg(2, 8).exec(0x5120).expect_pc(0x202);
+}
+
+#[test]
+fn skip_not_equal_variable() {
+    given().reg(1, 7).reg(2, 7).exec(0x9120).expect_pc(0x202);
+    given().reg(1, 7).reg(2, 8).exec(0x9120).expect_pc(0x204);
+}
+
+#[test]
+fn skip_key() {
+    given().reg(4, 0xA).key(0xA).exec(0xE49E).expect_pc(0x204);
+    given().reg(4, 0xA).exec(0xE49E).expect_pc(0x202);
+}
+
+#[test]
+fn skip_not_key() {
+    given().reg(4, 0xA).key(0xA).exec(0xE4A1).expect_pc(0x202);
+    given().reg(4, 0xA).exec(0xE4A1).expect_pc(0x204);
+}
+
+#[cfg(not(feature = "schip"))]
+#[test]
+fn classic_core_does_not_decode_variant_opcodes() {
+    use chippers_core::{
+        error::DecodeError,
+        instructions::{decode, Instruction},
+    };
+
+    assert_eq!(
+        decode(0x00FF),
+        Ok(Instruction::MachineRoutine { address: 0x0FF })
+    );
+    for opcode in [0xF175, 0xF185, 0xF030, 0xF000, 0xF201, 0xF002, 0xF03A] {
+        assert_eq!(decode(opcode), Err(DecodeError::InvalidOpcode(opcode)));
+    }
+}
+
+#[test]
+fn skip_key_uses_low_nibble() {
+    given().reg(0, 0x20).key(0x0).exec(0xE09E).expect_pc(0x204);
+    given().reg(0, 0xFA).exec(0xE09E).expect_pc(0x202);
+    given().reg(0, 0x2A).key(0xA).exec(0xE0A1).expect_pc(0x202);
+    given().reg(0, 0xFF).exec(0xE0A1).expect_pc(0x204);
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn skip_over_long_instruction() {
+    given()
+        .mem(0x202, &[0xF0, 0x00, 0x12, 0x34])
+        .reg(1, 0x42)
+        .exec(0x3142)
+        .expect_pc(0x206);
+}
+
+// Register setters
+
+#[test]
+fn set_with_byte() {
+    given().exec(0x6A5C).expect_reg(0xA, 0x5C);
+}
+
+#[test]
+fn set_with_variable() {
+    given().reg(2, 0x33).exec(0x8120).expect_reg(1, 0x33);
+}
+
+#[test]
+fn set_index_with_address() {
+    given().exec(0xA123).expect_i(0x123);
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn set_index_long() {
+    given()
+        .mem(0x202, &[0xBE, 0xEF])
+        .exec(0xF000)
+        .expect_i(0xBEEF)
+        .expect_pc(0x204);
+}
+
+#[test]
+fn set_index_with_font_address() {
+    given().reg(5, 0xB).exec(0xF529).expect_i(0xB * 5);
+    // Only the low nibble selects the character
+    given().reg(5, 0x1B).exec(0xF529).expect_i(0xB * 5);
+}
+
+// Arithmetic operations
+
+#[test]
+fn add_with_byte_wraps_without_flag() {
+    given()
+        .reg(3, 0xFF)
+        .reg(0xF, 0x7)
+        .exec(0x7302)
+        .expect_reg(3, 0x01)
+        .expect_vf(0x7);
+}
+
+#[test]
+fn add_with_variable() {
+    given()
+        .reg(3, 0x1F)
+        .reg(4, 0x1F)
+        .exec(0x8344)
+        .expect_reg(3, 0x3E)
+        .expect_vf(0);
+    given()
+        .reg(3, 0xF0)
+        .reg(4, 0x20)
+        .exec(0x8344)
+        .expect_reg(3, 0x10)
+        .expect_vf(1);
+}
+
+#[test]
+fn add_with_variable_into_vf_keeps_flag() {
+    given().reg(0xF, 0xFF).reg(1, 2).exec(0x8F14).expect_vf(1);
+}
+
+#[test]
+fn add_index_with_variable() {
+    given().i(0x300).reg(2, 0x20).exec(0xF21E).expect_i(0x320);
+}
+
+#[test]
+fn sub_with_variable() {
+    given()
+        .reg(1, 0x30)
+        .reg(2, 0x10)
+        .exec(0x8125)
+        .expect_reg(1, 0x20)
+        .expect_vf(1);
+    given()
+        .reg(1, 0x10)
+        .reg(2, 0x30)
+        .exec(0x8125)
+        .expect_reg(1, 0xE0)
+        .expect_vf(0);
+}
+
+#[test]
+fn sub_with_variable_not() {
+    given()
+        .reg(1, 0x10)
+        .reg(2, 0x30)
+        .exec(0x8127)
+        .expect_reg(1, 0x20)
+        .expect_vf(1);
+    given()
+        .reg(1, 0x30)
+        .reg(2, 0x10)
+        .exec(0x8127)
+        .expect_reg(1, 0xE0)
+        .expect_vf(0);
+}
+
+#[test]
+fn shift_right_reads_vy() {
+    given()
+        .reg(1, 0xFF)
+        .reg(2, 0b0000_0101)
+        .exec(0x8126)
+        .expect_reg(1, 0b0000_0010)
+        .expect_vf(1);
+}
+
+#[test]
+fn shift_left_reads_vy() {
+    given()
+        .reg(1, 0x00)
+        .reg(2, 0b1000_0001)
+        .exec(0x812E)
+        .expect_reg(1, 0b0000_0010)
+        .expect_vf(1);
+}
+
+#[test]
+fn shift_right_in_place() {
+    given()
+        .quirks(Quirks::MODERN)
+        .reg(1, 0b0000_0101)
+        .reg(2, 0xFF)
+        .exec(0x8126)
+        .expect_reg(1, 0b0000_0010)
+        .expect_vf(1);
+}
+
+#[test]
+fn shift_left_in_place() {
+    given()
+        .quirks(Quirks::MODERN)
+        .reg(1, 0b1000_0001)
+        .reg(2, 0x00)
+        .exec(0x812E)
+        .expect_reg(1, 0b0000_0010)
+        .expect_vf(1);
+}
+
+// Logical operations
+
+#[test]
+fn or() {
+    given()
+        .reg(1, 0b1100)
+        .reg(2, 0b1010)
+        .exec(0x8121)
+        .expect_reg(1, 0b1110);
+}
+
+#[test]
+fn and() {
+    given()
+        .reg(1, 0b1100)
+        .reg(2, 0b1010)
+        .exec(0x8122)
+        .expect_reg(1, 0b1000);
+}
+
+#[test]
+fn xor() {
+    given()
+        .reg(1, 0b1100)
+        .reg(2, 0b1010)
+        .exec(0x8123)
+        .expect_reg(1, 0b0110);
+}
+
+#[test]
+fn logical_operations_reset_vf() {
+    for opcode in [0x8121, 0x8122, 0x8123] {
+        given().reg(0xF, 7).exec(opcode).expect_vf(0);
+        given()
+            .quirks(Quirks::MODERN)
+            .reg(0xF, 7)
+            .exec(opcode)
+            .expect_vf(7);
+    }
+}
+
+// Display
+
+#[test]
+fn draw_sprite() {
+    given()
+        .mem(0x300, &[0b1111_0000, 0b1001_0000])
+        .i(0x300)
+        .reg(1, 2)
+        .reg(2, 5)
+        .exec(0xD122)
+        .expect_pixels(5, "..####..")
+        .expect_pixels(6, "..#..#..")
+        .expect_vf(0);
+}
+
+#[test]
+fn draw_sprite_collision_erases() {
+    given()
+        .mem(0x300, &[0b1100_0000])
+        .i(0x300)
+        .exec(0xD001)
+        .expect_vf(0)
+        .exec(0xD001)
+        .expect_pixels(0, "....")
+        .expect_vf(1);
+}
+
+#[test]
+fn draw_sprite_clips_at_edges() {
+    given()
+        .mem(0x300, &[0xFF, 0xFF])
+        .i(0x300)
+        .reg(1, 60)
+        .reg(2, 31)
+        .exec(0xD122)
+        .expect_pixels(0, &".".repeat(64))
+        .expect_pixels(31, &format!("{}####", ".".repeat(60)));
+}
+
+#[test]
+fn draw_sprite_wraps_at_edges() {
+    given()
+        .quirks(Quirks {
+            wrap_sprites: true,
+            ..Quirks::COSMAC
+        })
+        .mem(0x300, &[0xFF, 0xFF])
+        .i(0x300)
+        .reg(1, 60)
+        .reg(2, 31)
+        .exec(0xD122)
+        .expect_pixels(0, &format!("####{}####", ".".repeat(56)))
+        .expect_pixels(31, &format!("####{}####", ".".repeat(56)));
+}
+
+#[test]
+fn draw_sprite_wraps_coordinates() {
+    given()
+        .mem(0x300, &[0b1000_0000])
+        .i(0x300)
+        .reg(1, 64 + 3)
+        .reg(2, 32 + 1)
+        .exec(0xD121)
+        .expect_pixels(1, "...#");
+}
+
+#[test]
+fn clear_screen() {
+    given()
+        .mem(0x300, &[0xFF])
+        .i(0x300)
+        .exec(0xD001)
+        .exec(0x00E0)
+        .expect_pixels(0, "........");
+}
+
+// Timers
+
+#[test]
+fn set_variable_with_delay_timer() {
+    given().dt(0x25).exec(0xF307).expect_reg(3, 0x25);
+}
+
+#[test]
+fn set_delay_timer() {
+    given().reg(3, 0x25).exec(0xF315).expect_dt(0x25);
+}
+
+#[test]
+fn set_sound_timer() {
+    given().reg(3, 0x25).exec(0xF318).expect_st(0x25);
+}
+
+// RAM load and store
+
+#[test]
+fn store_registers_increments_index() {
+    given()
+        .reg(0, 1)
+        .reg(1, 2)
+        .reg(2, 3)
+        .i(0x300)
+        .exec(0xF255)
+        .expect_mem(0x300, &[1, 2, 3])
+        .expect_i(0x303);
+}
+
+#[test]
+fn load_into_registers_increments_index() {
+    given()
+        .mem(0x300, &[1, 2, 3])
+        .i(0x300)
+        .exec(0xF265)
+        .expect_reg(0, 1)
+        .expect_reg(1, 2)
+        .expect_reg(2, 3)
+        .expect_i(0x303);
+}
+
+#[test]
+fn store_registers_keeps_index() {
+    given()
+        .quirks(Quirks::MODERN)
+        .reg(0, 1)
+        .reg(1, 2)
+        .reg(2, 3)
+        .i(0x300)
+        .exec(0xF255)
+        .expect_mem(0x300, &[1, 2, 3])
+        .expect_i(0x300);
+}
+
+#[test]
+fn load_into_registers_keeps_index() {
+    given()
+        .quirks(Quirks::MODERN)
+        .mem(0x300, &[1, 2, 3])
+        .i(0x300)
+        .exec(0xF265)
+        .expect_reg(0, 1)
+        .expect_reg(1, 2)
+        .expect_reg(2, 3)
+        .expect_i(0x300);
+}
+
+// Misc
+
+#[test]
+fn store_decimal_conversion() {
+    given()
+        .reg(7, 254)
+        .i(0x300)
+        .exec(0xF733)
+        .expect_mem(0x300, &[2, 5, 4])
+        .expect_i(0x300);
+}
+
+#[test]
+fn wait_for_key_blocks_until_released() {
+    let mut state = given().exec(0xF50A).exec(0x6001);
+    // Still waiting, nothing was executed
+    state = state.expect_pc(0x202).expect_reg(0, 0);
+
+    state.interpreter.input_handler.press(Key::KeyC);
+    state.interpreter.input_handler.release(Key::KeyC);
+    state.exec(0x6001).expect_reg(5, 0xC).expect_reg(0, 1);
+}
+
+#[test]
+fn wait_for_key_on_press() {
+    let state = given()
+        .quirks(Quirks {
+            key_wait: KeyWait::Press,
+            ..Quirks::COSMAC
+        })
+        .exec(0xF50A)
+        .key(0x3)
+        .exec(0x6001)
+        .expect_reg(5, 0x3)
+        .expect_reg(0, 1);
+    assert_eq!(state.interpreter.input_handler.waiting(), None);
+}
+
+#[test]
+fn wait_for_key_ignores_keys_held_before() {
+    given()
+        .quirks(Quirks {
+            key_wait: KeyWait::Press,
+            ..Quirks::COSMAC
+        })
+        .key(0x3)
+        .exec(0xF50A)
+        .exec(0x6001)
+        .expect_pc(0x202)
+        .expect_reg(0, 0);
+}
+
+#[test]
+fn wait_for_key_with_tone() {
+    given()
+        .quirks(Quirks {
+            key_wait: KeyWait::ReleaseWithTone,
+            ..Quirks::COSMAC
+        })
+        .exec(0xF50A)
+        .key(0x3)
+        .exec(0x6001)
+        .expect_pc(0x202)
+        .expect_st(4);
+}
+
+#[test]
+fn random_and_masks() {
+    given().reg(2, 0xFF).exec(0xC200).expect_reg(2, 0);
+    for _ in 0..16 {
+        let state = given().exec(0xC20F);
+        assert_eq!(state.interpreter.variable_registers[2] & 0xF0, 0);
+    }
+}
+
+#[test]
+fn random_and_uses_given_rng() {
+    let mut state = given();
+    state.interpreter.set_rng(StepRng::new(0xAB, 0));
+    state.exec(0xC20F).expect_reg(2, 0x0B);
+}
+
+#[test]
+fn machine_routine_is_ignored() {
+    given().exec(0x0123).expect_pc(0x202);
+}
+
+#[test]
+fn invalid_opcode_fails() {
+    assert!(matches!(
+        given().try_exec(0x5121),
+        Err(InterpreterError::Decode(_))
+    ));
+}
+
+// SUPER-CHIP
+
+#[cfg(feature = "schip")]
+#[test]
+fn resolution_switch_clears_screen() {
+    let state = given()
+        .mem(0x300, &[0xFF])
+        .i(0x300)
+        .exec(0xD001)
+        .exec(0x00FF)
+        .expect_pixels(0, "........");
+    assert_eq!(state.interpreter.screen.width(), 128);
+    assert_eq!(state.interpreter.screen.height(), 64);
+
+    let state = state.exec(0x00FE);
+    assert_eq!(state.interpreter.screen.width(), 64);
+    assert_eq!(state.interpreter.screen.height(), 32);
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn draw_large_sprite() {
+    let state = given()
+        .mem(0x300, &[0xFF, 0x01].repeat(16))
+        .i(0x300)
+        .exec(0x00FF)
+        .reg(1, 120)
+        .reg(2, 60)
+        .exec(0xD120)
+        .expect_pixels(60, &format!("{}{}", ".".repeat(120), "########"))
+        .expect_pixels(63, &format!("{}{}", ".".repeat(120), "########"))
+        .expect_vf(0);
+    assert!(!state.interpreter.screen.get(127, 0));
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn scroll_down() {
+    given()
+        .mem(0x300, &[0b1000_0000])
+        .i(0x300)
+        .exec(0xD001)
+        .exec(0x00C3)
+        .expect_pixels(0, "..")
+        .expect_pixels(3, "#.");
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn scroll_right_and_left() {
+    let state = given()
+        .mem(0x300, &[0b1000_0001])
+        .i(0x300)
+        .reg(1, 56)
+        .exec(0xD101)
+        .exec(0x00FB)
+        .expect_pixels(0, &format!("{}....#...", ".".repeat(56)));
+    // Pixels scrolled past the right edge are lost
+    state
+        .exec(0x00FC)
+        .expect_pixels(0, &format!("{}#.......", ".".repeat(56)));
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn draw_into_both_planes() {
+    // The second plane's sprite follows the first one's
+    let state = given()
+        .mem(0x300, &[0b1100_0000, 0b1010_0000])
+        .i(0x300)
+        .exec(0xF301)
+        .exec(0xD001);
+    let color_indices: Vec<u8> = (0..3)
+        .map(|x| state.interpreter.screen.color_index(x, 0))
+        .collect();
+    assert_eq!(color_indices, [0b11, 0b01, 0b10]);
+    let rows: Vec<u128> = state.interpreter.screen.rows().collect();
+    assert_eq!(rows.len(), 32);
+    assert_eq!(rows[0], 0b111 << 125);
+    assert_eq!(state.interpreter.screen.as_bits()[1][0], 0b101 << 125);
+
+    // Clearing only affects the selected planes
+    let state = state.exec(0xF201).exec(0x00E0);
+    assert_eq!(state.interpreter.screen.color_index(0, 0), 0b01);
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn audio_pattern_and_pitch() {
+    let pattern = [0xAA; 16];
+    let mut state = given()
+        .mem(0x300, &pattern)
+        .i(0x300)
+        .exec(0xF002)
+        .reg(1, 112)
+        .exec(0xF13A);
+    assert_eq!(state.interpreter.audio_pattern(), &pattern);
+    assert_eq!(state.interpreter.pitch(), 112);
+    // 48 pitch steps up doubles the rate
+    assert!((state.interpreter.playback_rate() - 8000.0).abs() < 1.0);
+
+    // Silent until the sound timer runs, then one pattern bit per sample at 8000 Hz
+    let mut buffer = [true; 4];
+    state.interpreter.sound_buffer(8000, &mut buffer);
+    assert_eq!(buffer, [false; 4]);
+    state.interpreter.sound_timer.value = 10;
+    state.interpreter.sound_buffer(8000, &mut buffer);
+    assert_eq!(buffer, [true, false, true, false]);
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn set_index_with_big_font_address() {
+    given()
+        .reg(5, 7)
+        .exec(0xF530)
+        .expect_i(0x50 + 7 * 10)
+        .expect_mem(0x50 + 7 * 10, &[0xFF, 0xFF]);
+    given()
+        .reg(5, 0x1E)
+        .exec(0xF530)
+        .expect_i(0x50 + 0xE * 10)
+        .expect_mem(0x50 + 0xE * 10, &[0xFF, 0xFF, 0xC0]);
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn store_and_load_flags() {
+    given()
+        .reg(0, 1)
+        .reg(1, 2)
+        .reg(2, 3)
+        .exec(0xF275)
+        .reg(0, 0)
+        .reg(1, 0)
+        .reg(2, 0)
+        .exec(0xF185)
+        .expect_reg(0, 1)
+        .expect_reg(1, 2)
+        .expect_reg(2, 0);
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn exit_halts() {
+    let state = given()
+        .exec(0x00FD)
+        .exec(0x6001)
+        .expect_pc(0x202)
+        .expect_reg(0, 0);
+    assert!(state.interpreter.is_halted());
+}
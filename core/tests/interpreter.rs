@@ -0,0 +1,601 @@
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chippers_core::{
+    error::{InterpreterError, LoadError},
+    instructions::Instruction,
+    interpreter::{
+        ClockConfig, HookAction, IllegalInstructionAction, Interpreter, Key, KeyEvent,
+        MemoryProtection, Quirks, Status,
+    },
+};
+
+use common::{given, IDLE};
+
+#[test]
+fn status() {
+    let state = given();
+    assert!(matches!(state.interpreter.status(), Status::Running));
+
+    let state = state.exec(0xF50A);
+    assert!(matches!(
+        state.interpreter.status(),
+        Status::WaitingForKey { register: 5 }
+    ));
+
+    #[cfg(feature = "schip")]
+    {
+        let state = given().exec(0x00FD);
+        assert!(matches!(state.interpreter.status(), Status::Halted));
+    }
+
+    let mut state = given().mem(0x200, &[0x00, 0xEE]);
+    assert!(state.interpreter.step().is_err());
+    assert!(matches!(
+        state.interpreter.status(),
+        Status::Errored(InterpreterError::StackUnderflow { .. })
+    ));
+    // Errors are sticky
+    assert!(state.interpreter.step().is_err());
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn flags_go_through_storage() {
+    use chippers_core::flags::FlagStorage;
+
+    struct Shared(Arc<Mutex<[u8; 16]>>);
+
+    impl FlagStorage for Shared {
+        fn load(&mut self) -> [u8; 16] {
+            *self.0.lock().unwrap()
+        }
+
+        fn save(&mut self, flags: &[u8; 16]) {
+            *self.0.lock().unwrap() = *flags;
+        }
+    }
+
+    let saved = Arc::new(Mutex::new([0; 16]));
+    let mut state = given();
+    state.interpreter.set_flag_storage(Shared(saved.clone()));
+    state.reg(0, 0xAB).reg(1, 0xCD).exec(0xF175);
+    assert_eq!(saved.lock().unwrap()[..3], [0xAB, 0xCD, 0]);
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn extended_memory() {
+    let mut interpreter = Interpreter::builder()
+        .memory_size(0x10000)
+        .build(&[0xF0, 0x00, 0xFF, 0xF0, 0xF3, 0x55])
+        .unwrap();
+    interpreter.variable_registers[3] = 0x99;
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.ram[0xFFF3], 0x99);
+    assert_eq!(interpreter.ram.len(), 0x10000);
+
+    assert!(Interpreter::builder()
+        .memory_size(0x10001)
+        .build(&IDLE)
+        .is_err());
+}
+
+#[test]
+fn alternate_start_address() {
+    let mut interpreter = Interpreter::builder()
+        .start_address(0x600)
+        .build(&[0x61, 0x23])
+        .unwrap();
+    assert_eq!(interpreter.program_counter, 0x600);
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.variable_registers[1], 0x23);
+
+    // Reloading keeps the start address
+    interpreter.load_new_rom(&[0x62, 0x34]).unwrap();
+    assert_eq!(interpreter.program_counter, 0x600);
+    assert_eq!(interpreter.ram[0x601], 0x34);
+}
+
+#[test]
+fn custom_font() {
+    let font = [0x11; 80];
+    let big_font = [0x22; 160];
+    let mut interpreter = Interpreter::builder()
+        .font(&font)
+        .big_font(&big_font)
+        .build(&IDLE)
+        .unwrap();
+    assert_eq!(interpreter.ram[0..80], font);
+    assert_eq!(interpreter.ram[0x50..0xF0], big_font);
+
+    // Reloading keeps the custom fonts
+    interpreter.load_new_rom(&IDLE).unwrap();
+    assert_eq!(interpreter.ram[0..80], font);
+}
+
+#[test]
+fn small_memory() {
+    let builder = || Interpreter::builder().memory_size(2048);
+    assert_eq!(builder().build(&[0; 0x600]).unwrap().ram.len(), 2048);
+
+    // Layouts that don't fit are reported instead of panicking
+    assert!(matches!(
+        builder().build(&[0; 0x601]),
+        Err(LoadError::TooLarge {
+            size: 0x601,
+            max: 0x600
+        })
+    ));
+    assert!(matches!(
+        builder().start_address(0x900).build(&IDLE),
+        Err(LoadError::TooLarge { size: 2, max: 0 })
+    ));
+    assert!(matches!(
+        Interpreter::builder().memory_size(0x200).build(&IDLE),
+        Err(LoadError::InvalidMemorySize { size: 0x200 })
+    ));
+}
+
+#[test]
+fn memory_protection() {
+    let store = |protection| {
+        let mut state = given().reg(0, 0xAB).i(0x1FF);
+        state.interpreter.memory_protection = protection;
+        state.try_exec(0xF055)
+    };
+    store(MemoryProtection::Allow)
+        .unwrap()
+        .expect_mem(0x1FF, &[0xAB]);
+    store(MemoryProtection::Ignore)
+        .unwrap()
+        .expect_mem(0x1FF, &[0x00]);
+    assert!(matches!(
+        store(MemoryProtection::Error),
+        Err(InterpreterError::ProtectedWrite { address: 0x1FF })
+    ));
+}
+
+#[test]
+fn deterministic_runs_match() {
+    // Loops drawing random values while the delay timer counts down
+    let program = [
+        0x60, 0x05, 0xF0, 0x15, 0xC1, 0xFF, 0xF2, 0x07, 0x32, 0x00, 0x12, 0x04,
+    ];
+    let run = || {
+        let mut interpreter = Interpreter::builder()
+            .deterministic(42, 4)
+            .build(&program)
+            .unwrap();
+        for _ in 0..40 {
+            interpreter.step().unwrap();
+        }
+        (interpreter.variable_registers[1], interpreter.frames())
+    };
+    assert_eq!(run(), run());
+    assert_eq!(run().1, 9);
+}
+
+#[test]
+fn empty_program_is_rejected() {
+    assert!(matches!(Interpreter::new(&[]), Err(LoadError::Empty)));
+    // The current program stays loaded
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    assert!(matches!(
+        interpreter.load_new_rom(&[]),
+        Err(LoadError::Empty)
+    ));
+    assert_eq!(interpreter.ram[0x200], 0x12);
+}
+
+#[test]
+fn out_of_bounds_access() {
+    assert!(matches!(
+        given().i(0xFFF).try_exec(0xF155),
+        Err(InterpreterError::OutOfBoundsAccess { address: 0x1000 })
+    ));
+    assert!(matches!(
+        given().i(0x2000).try_exec(0xD005),
+        Err(InterpreterError::OutOfBoundsAccess { address: 0x2000 })
+    ));
+
+    // With the quirk, addresses wrap around to the start of memory
+    given()
+        .quirks(Quirks {
+            wrap_memory: true,
+            ..Quirks::COSMAC
+        })
+        .reg(0, 0xAA)
+        .reg(1, 0xBB)
+        .i(0xFFF)
+        .exec(0xF155)
+        .expect_mem(0xFFF, &[0xAA])
+        .expect_mem(0x000, &[0xBB]);
+}
+
+#[test]
+fn step_outcome() {
+    let mut state = given().reg(1, 5);
+    state = state.mem(0x200, &[0xF1, 0x18]);
+    let outcome = state.interpreter.step().unwrap();
+    assert_eq!(outcome.cycles, 1);
+    assert!(outcome.sound_started);
+    assert!(!outcome.screen_changed);
+
+    state = state.mem(0x202, &[0x00, 0xE0]);
+    assert!(state.interpreter.step().unwrap().screen_changed);
+
+    state = state.mem(0x204, &[0xF2, 0x0A]);
+    state.interpreter.step().unwrap();
+    let outcome = state.interpreter.step().unwrap();
+    assert_eq!(outcome.cycles, 0);
+    assert!(outcome.waiting_for_key);
+}
+
+#[test]
+fn run_frame_ticks_timers_once() {
+    // Sets the delay timer, then counts in V0
+    let program = [0x60, 0x05, 0xF0, 0x15, 0x70, 0x01, 0x12, 0x04];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    let frame = interpreter.run_frame(10).unwrap();
+    assert_eq!(frame.cycles, 10);
+    assert_eq!(interpreter.delay_timer.value, 4);
+    assert_eq!(interpreter.frames(), 1);
+}
+
+#[test]
+fn key_events_apply_at_frame_boundaries() {
+    // Counts in V0 forever
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    let event = |key, pressed, frame| KeyEvent {
+        key,
+        pressed,
+        frame,
+    };
+    interpreter.queue_key_event(event(Key::Key2, true, 2));
+    interpreter.queue_key_event(event(Key::Key1, true, 0));
+    interpreter.queue_key_event(event(Key::Key1, false, 0));
+
+    // Nothing changes until a frame starts
+    assert!(!interpreter.input_handler.is_pressed(Key::Key1));
+
+    // A press and release within one frame is held for that frame
+    interpreter.run_frame(1).unwrap();
+    assert!(interpreter.input_handler.is_pressed(Key::Key1));
+    interpreter.run_frame(1).unwrap();
+    assert!(!interpreter.input_handler.is_pressed(Key::Key1));
+    assert!(!interpreter.input_handler.is_pressed(Key::Key2));
+
+    interpreter.run_frame(1).unwrap();
+    assert!(interpreter.input_handler.is_pressed(Key::Key2));
+}
+
+#[test]
+fn key_latency_is_recorded() {
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    let event = |key, pressed, frame| KeyEvent {
+        key,
+        pressed,
+        frame,
+    };
+    interpreter.queue_key_event(event(Key::Key3, true, 0));
+    interpreter.run_frame(1).unwrap();
+    // Off by default
+    assert!(interpreter.take_applied_key_events().is_empty());
+
+    interpreter.record_key_latency = true;
+    // A press and release queued for the same frame, the release waits a frame
+    interpreter.queue_key_event(event(Key::Key1, true, 1));
+    interpreter.queue_key_event(event(Key::Key1, false, 1));
+    // Queued for a frame that has already started, it's applied first
+    interpreter.queue_key_event(event(Key::Key2, true, 0));
+    for _ in 0..3 {
+        interpreter.run_frame(1).unwrap();
+    }
+
+    let applied = interpreter.take_applied_key_events();
+    let latencies: Vec<(Key, bool, u64, u64)> = applied
+        .iter()
+        .map(|applied| {
+            let event = applied.event;
+            (
+                event.key,
+                event.pressed,
+                applied.applied_at,
+                applied.latency(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        latencies,
+        [
+            (Key::Key2, true, 1, 1),
+            (Key::Key1, true, 1, 0),
+            (Key::Key1, false, 2, 1),
+        ]
+    );
+    assert!(interpreter.take_applied_key_events().is_empty());
+}
+
+#[test]
+fn run_for_follows_clock() {
+    // Counts in V0 forever
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    interpreter.clock = ClockConfig::per_second(500);
+
+    // Rates that don't divide evenly still add up over a second
+    let summary = interpreter.run_for(Duration::from_secs(1)).unwrap();
+    assert_eq!(summary.cycles, 500);
+    assert_eq!(interpreter.frames(), 60);
+
+    // Less than a frame is carried over
+    let half_frame = interpreter.clock.frame_duration() / 2;
+    assert_eq!(interpreter.run_for(half_frame).unwrap().cycles, 0);
+    assert_eq!(interpreter.run_for(half_frame).unwrap().cycles, 8);
+}
+
+#[test]
+fn run_frames_fast_forwards() {
+    // Draws V0's digit while counting in V0
+    let program = [0xF0, 0x29, 0xD1, 0x15, 0x70, 0x01, 0x12, 0x00];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.clock = ClockConfig::per_frame(8);
+
+    // Several frames reported as one, for a single redraw
+    let summary = interpreter.run_frames(6).unwrap();
+    assert_eq!(interpreter.frames(), 6);
+    assert_eq!(summary.cycles, 48);
+    assert!(summary.screen_changed);
+
+    // Time left over from run_for is kept
+    let half_frame = interpreter.clock.frame_duration() / 2;
+    interpreter.run_for(half_frame).unwrap();
+    interpreter.run_frames(1).unwrap();
+    assert_eq!(interpreter.frames(), 7);
+    interpreter.run_for(half_frame).unwrap();
+    assert_eq!(interpreter.frames(), 8);
+}
+
+#[test]
+fn clock_extremes() {
+    assert_eq!(
+        ClockConfig::per_frame(u32::MAX).instructions_per_second,
+        u32::MAX
+    );
+
+    // A zero timer rate runs as 1 Hz instead of dividing by zero
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    interpreter.clock = ClockConfig {
+        instructions_per_second: 10,
+        timer_hz: 0,
+    };
+    assert_eq!(interpreter.clock.frame_duration(), Duration::from_secs(1));
+    assert_eq!(
+        interpreter.run_for(Duration::from_secs(2)).unwrap().cycles,
+        20
+    );
+    assert_eq!(interpreter.frames(), 2);
+
+    // Rates above a billion ticks per second still advance time
+    interpreter.clock.timer_hz = u32::MAX;
+    assert_eq!(interpreter.clock.frame_duration(), Duration::from_nanos(1));
+}
+
+#[test]
+fn self_jump_finishes() {
+    let state = given().exec(0x1200);
+    assert!(matches!(state.interpreter.status(), Status::Finished));
+    // Nothing runs past it
+    let mut state = state.mem(0x200, &[0x00, 0xE0]);
+    assert_eq!(state.interpreter.step().unwrap().cycles, 0);
+
+    let state = given().exec(0x1202);
+    assert!(matches!(state.interpreter.status(), Status::Running));
+}
+
+#[test]
+fn hooks() {
+    let traced = Arc::new(Mutex::new(Vec::new()));
+    let mut state = given();
+    state
+        .interpreter
+        .set_hook(|_, instruction| match instruction {
+            Instruction::SetWithByte { register: 1, .. } => HookAction::Skip,
+            Instruction::ClearScreen => HookAction::Abort,
+            _ => HookAction::Continue,
+        });
+    let trace = traced.clone();
+    state.interpreter.set_post_hook(move |interpreter, _| {
+        trace.lock().unwrap().push(interpreter.program_counter)
+    });
+
+    let state = state
+        .exec(0x6105)
+        .expect_reg(1, 0)
+        .exec(0x6205)
+        .expect_reg(2, 5);
+    assert_eq!(*traced.lock().unwrap(), [0x204]);
+    assert!(matches!(
+        state.try_exec(0x00E0),
+        Err(InterpreterError::Aborted { address: 0x204 })
+    ));
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn hook_skips_long_instruction() {
+    let program = [
+        0xF0, 0x00, 0x61, 0x23, // LD I, 0x6123
+        0x62, 0x01, // LD V2, 0x01
+    ];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.set_hook(|_, instruction| match instruction {
+        Instruction::SetIndexLong => HookAction::Skip,
+        _ => HookAction::Continue,
+    });
+
+    // The operand word isn't executed as `LD V1, 0x23`
+    let outcome = interpreter.step().unwrap();
+    assert_eq!(outcome.cycles, 1);
+    assert_eq!(interpreter.cycles(), 1);
+    assert_eq!(interpreter.program_counter, 0x204);
+    assert_eq!(interpreter.index_register, 0);
+
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.variable_registers[1], 0);
+    assert_eq!(interpreter.variable_registers[2], 1);
+}
+
+#[test]
+fn machine_routine_handler() {
+    let mut state = given();
+    state
+        .interpreter
+        .set_machine_routine_handler(|interpreter, address| {
+            match address {
+                // Emulate a routine clearing V0
+                0x0123 => interpreter.variable_registers[0] = 0,
+                _ => return Err(InterpreterError::Aborted { address }),
+            }
+            Ok(())
+        });
+
+    let state = state.reg(0, 7).exec(0x0123).expect_reg(0, 0);
+    assert!(matches!(
+        state.try_exec(0x0456),
+        Err(InterpreterError::Aborted { address: 0x456 })
+    ));
+}
+
+#[test]
+fn illegal_instruction_handler() {
+    let mut state = given();
+    state
+        .interpreter
+        .set_illegal_instruction_handler(|interpreter, opcode| match opcode {
+            // A made-up extension setting V0
+            0x5001 => {
+                interpreter.variable_registers[0] = 0x42;
+                IllegalInstructionAction::Skip
+            }
+            0xFFFF => IllegalInstructionAction::Halt,
+            _ => IllegalInstructionAction::Error,
+        });
+
+    let state = state.exec(0x5001).expect_reg(0, 0x42).expect_pc(0x202);
+    let state = state.exec(0xFFFF);
+    assert!(state.interpreter.is_halted());
+
+    let mut state = given();
+    state
+        .interpreter
+        .set_illegal_instruction_handler(|_, _| IllegalInstructionAction::Error);
+    assert!(matches!(
+        state.try_exec(0x5001),
+        Err(InterpreterError::Decode(_))
+    ));
+}
+
+#[test]
+fn idle_loop_skipping() {
+    let program = [
+        0x60, 0x05, // LD V0, 5
+        0xF0, 0x15, // LD DT, V0
+        0xF1, 0x07, // LD V1, DT
+        0x31, 0x00, // SE V1, 0
+        0x12, 0x04, // JP 0x204
+        0x62, 0x01, // LD V2, 1
+        0x12, 0x0C, // JP 0x20C
+    ];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.idle_loop_skipping = true;
+
+    // The frame ends as soon as the loop is reached, with the timer run out
+    let frame = interpreter.run_frame(10).unwrap();
+    assert_eq!(frame.skipped_frames, 5);
+    assert_eq!(frame.cycles, 3);
+    assert_eq!(interpreter.frames(), 5);
+    assert_eq!(interpreter.delay_timer.value, 0);
+
+    interpreter.run_frame(3).unwrap();
+    assert_eq!(interpreter.variable_registers[2], 1);
+
+    // Without skipping, the loop spins for every frame
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    let frame = interpreter.run_frame(10).unwrap();
+    assert_eq!(frame.skipped_frames, 0);
+    assert_eq!(frame.cycles, 10);
+    assert_eq!(interpreter.frames(), 1);
+}
+
+#[test]
+fn pause_freezes_execution_and_timers() {
+    // Counts in V0 forever
+    let mut state = given().mem(0x200, &[0x70, 0x01, 0x12, 0x00]).dt(10);
+    state.interpreter.pause();
+    assert!(state.interpreter.is_paused());
+    assert!(matches!(state.interpreter.status(), Status::Paused));
+
+    let frame = state.interpreter.run_frame(10).unwrap();
+    assert_eq!(frame.cycles, 0);
+    assert_eq!(state.interpreter.frames(), 0);
+    assert_eq!(state.interpreter.delay_timer.value, 10);
+
+    state.interpreter.resume();
+    assert!(!state.interpreter.is_paused());
+    assert_eq!(state.interpreter.run_frame(10).unwrap().cycles, 10);
+    assert_eq!(state.interpreter.delay_timer.value, 9);
+}
+
+#[test]
+fn keys_pressed_while_paused_do_not_end_key_wait() {
+    let mut state = given().exec(0xF50A);
+    state.interpreter.pause();
+    state.interpreter.input_handler.press(Key::from(3));
+    state.interpreter.resume();
+
+    // The key was already down when the wait restarted
+    state.interpreter.input_handler.release(Key::from(3));
+    state.interpreter.step().unwrap();
+    assert!(matches!(
+        state.interpreter.status(),
+        Status::WaitingForKey { register: 5 }
+    ));
+
+    state.interpreter.input_handler.press(Key::from(4));
+    state.interpreter.input_handler.release(Key::from(4));
+    state.interpreter.step().unwrap();
+    assert_eq!(state.interpreter.variable_registers[5], 4);
+}
+
+#[test]
+fn load_new_rom_resets_machine() {
+    let mut interpreter = Interpreter::new(&[0x60, 0x01, 0x23, 0x00]).unwrap();
+    interpreter.run_frame(2).unwrap();
+    assert_eq!(interpreter.stack.depth(), 1);
+    let memory = interpreter.ram[0..1].as_ptr();
+
+    // A program that doesn't fit leaves the current one in place
+    assert!(interpreter.load_new_rom(&[0; 0x1000]).is_err());
+    assert_eq!(interpreter.ram[0x200..0x204], [0x60, 0x01, 0x23, 0x00]);
+
+    interpreter.load_new_rom(&[0x61, 0x02]).unwrap();
+    // Memory is reused rather than reallocated, fonts included
+    assert_eq!(interpreter.ram[0..1].as_ptr(), memory);
+    assert_eq!(interpreter.ram[0..5], [0xF0, 0x90, 0x90, 0x90, 0xF0]);
+    assert_eq!(interpreter.program_counter, 0x200);
+    assert_eq!(interpreter.variable_registers[0], 0);
+    assert_eq!(interpreter.stack.depth(), 0);
+    assert_eq!(interpreter.cycles(), 0);
+    assert_eq!(interpreter.frames(), 0);
+    // Leftovers of the previous program are cleared
+    assert_eq!(interpreter.ram[0x202..0x204], [0, 0]);
+
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.variable_registers[1], 2);
+}
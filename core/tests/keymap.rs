@@ -0,0 +1,57 @@
+use chippers_core::{
+    error::LayoutError,
+    interpreter::Key,
+    keymap::{scancode_key, Keymap, Layout},
+};
+
+#[test]
+fn presets_map_every_key_once() {
+    for layout in Layout::ALL {
+        let keymap = Keymap::preset(layout);
+        let mut characters: Vec<char> = (0..16)
+            .map(|key| keymap.character(Key::from(key)))
+            .collect();
+        characters.sort();
+        characters.dedup();
+        assert_eq!(characters.len(), 16, "{layout:?}");
+
+        for key in 0..16 {
+            let key = Key::from(key);
+            assert_eq!(keymap.key(keymap.character(key)), Some(key), "{layout:?}");
+        }
+    }
+}
+
+#[test]
+fn presets_keep_the_keypad_shape() {
+    // The bottom-right key is V on QWERTY, and at the same place on the others
+    let bottom_right: Vec<char> = Layout::ALL
+        .into_iter()
+        .map(|layout| Keymap::preset(layout).character(Key::KeyF))
+        .collect();
+    assert_eq!(bottom_right, ['v', 'v', 'v', 'k', 'v']);
+
+    let keymap = Keymap::COLEMAK;
+    assert_eq!(keymap.key('F'), Some(Key::Key6));
+    assert_eq!(keymap.key('d'), None);
+    assert_eq!(Keymap::AZERTY.key('a'), Some(Key::Key4));
+}
+
+#[test]
+fn layout_names() {
+    assert_eq!("colemak".parse(), Ok(Layout::Colemak));
+    assert_eq!("QWERTZ".parse(), Ok(Layout::Qwertz));
+    assert_eq!(
+        "bepo".parse::<Layout>(),
+        Err(LayoutError::Unknown("bepo".into()))
+    );
+}
+
+#[test]
+fn scancodes_are_positional() {
+    assert_eq!(scancode_key(0x02), Some(Key::Key1));
+    assert_eq!(scancode_key(0x05), Some(Key::KeyC));
+    assert_eq!(scancode_key(0x2F), Some(Key::KeyF));
+    assert_eq!(scancode_key(0x06), None);
+    assert_eq!(scancode_key(0x00), None);
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use chippers_core::{
+    core::{Banks, Segment},
+    error::{InterpreterError, LoadError},
+    interpreter::{Interpreter, SelfModifyingCode, SelfModifyingWrite},
+};
+
+use common::IDLE;
+
+#[test]
+fn load_segments() {
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    interpreter
+        .load_segments(&[
+            Segment {
+                address: 0x800,
+                data: &[1, 2],
+            },
+            Segment {
+                address: 0xFFE,
+                data: &[3, 4],
+            },
+        ])
+        .unwrap();
+    assert_eq!(interpreter.ram[0x800..0x802], [1, 2]);
+    assert_eq!(interpreter.ram[0xFFE..0x1000], [3, 4]);
+
+    assert!(matches!(
+        interpreter.load_segments(&[Segment {
+            address: 0xFFF,
+            data: &[5, 6],
+        }]),
+        Err(LoadError::SegmentOutOfBounds {
+            address: 0xFFF,
+            size: 2
+        })
+    ));
+}
+
+#[test]
+fn bank_switching_keeps_writes() {
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    let banks = Banks::new(0x800, vec![vec![1; 4], vec![2; 4]]).unwrap();
+    interpreter.set_banks(banks).unwrap();
+    assert_eq!(interpreter.ram[0x800..0x804], [1; 4]);
+
+    interpreter.ram[0x800] = 9;
+    interpreter.switch_bank(1).unwrap();
+    assert_eq!(interpreter.banks().unwrap().selected(), 1);
+    assert_eq!(interpreter.ram[0x800..0x804], [2; 4]);
+
+    // The write made while bank 0 was mapped was saved back into it
+    interpreter.switch_bank(0).unwrap();
+    assert_eq!(interpreter.ram[0x800..0x804], [9, 1, 1, 1]);
+
+    assert!(matches!(
+        interpreter.switch_bank(2),
+        Err(LoadError::InvalidBank { bank: 2 })
+    ));
+}
+
+#[test]
+fn banks_must_match_in_size() {
+    assert!(matches!(
+        Banks::new(0x800, vec![vec![0; 4], vec![0; 4], vec![0; 2]]),
+        Err(LoadError::BankSizeMismatch { bank: 2 })
+    ));
+
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    assert!(matches!(
+        interpreter.switch_bank(0),
+        Err(LoadError::InvalidBank { bank: 0 })
+    ));
+    let banks = Banks::new(0xFFE, vec![vec![0; 4]]).unwrap();
+    assert!(interpreter.set_banks(banks).is_err());
+}
+
+// Stores V0 over its own first instruction, then over data that never ran
+const SELF_MODIFYING: [u8; 12] = [
+    0xA2, 0x00, // LD I, 0x200
+    0x60, 0xAA, // LD V0, 0xAA
+    0xF0, 0x55, // LD [I], V0
+    0xA3, 0x00, // LD I, 0x300
+    0xF0, 0x55, // LD [I], V0
+    0x12, 0x0A, // JP 0x20A
+];
+
+#[test]
+fn self_modifying_writes_are_reported() {
+    let mut interpreter = Interpreter::new(&SELF_MODIFYING).unwrap();
+    interpreter.self_modifying_code = SelfModifyingCode::Report;
+    for _ in 0..5 {
+        interpreter.step().unwrap();
+    }
+
+    assert_eq!(interpreter.ram[0x200u16], 0xAA);
+    assert_eq!(interpreter.ram[0x300u16], 0xAA);
+    assert_eq!(
+        interpreter.take_self_modifying_writes(),
+        [SelfModifyingWrite {
+            pc: 0x204,
+            address: 0x200
+        }]
+    );
+    assert!(interpreter.take_self_modifying_writes().is_empty());
+}
+
+#[test]
+fn self_modifying_writes_can_trap() {
+    let mut interpreter = Interpreter::new(&SELF_MODIFYING).unwrap();
+    interpreter.self_modifying_code = SelfModifyingCode::Trap;
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+
+    assert!(matches!(
+        interpreter.step(),
+        Err(InterpreterError::SelfModifyingWrite {
+            pc: 0x204,
+            address: 0x200
+        })
+    ));
+    assert_eq!(interpreter.ram[0x200u16], 0xA2);
+}
+
+#[test]
+fn self_modifying_writes_are_ignored_by_default() {
+    let mut interpreter = Interpreter::new(&SELF_MODIFYING).unwrap();
+    for _ in 0..5 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(interpreter.ram[0x200u16], 0xAA);
+    assert!(interpreter.take_self_modifying_writes().is_empty());
+}
+
+#[test]
+fn self_modifying_write_at_end_of_memory() {
+    let mut interpreter = Interpreter::builder()
+        .memory_size(0x10000)
+        .build(&[0xA2, 0x00]) // LD I, 0x200
+        .unwrap();
+    interpreter.self_modifying_code = SelfModifyingCode::Report;
+    interpreter.ram[0xFFFE] = 0xF0;
+    interpreter.ram[0xFFFF] = 0x55;
+    interpreter.step().unwrap();
+
+    // The program counter wraps to 0 once the write at 0xFFFE is fetched
+    interpreter.program_counter = 0xFFFE;
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.program_counter, 0);
+    assert_eq!(
+        interpreter.take_self_modifying_writes(),
+        [SelfModifyingWrite {
+            pc: 0xFFFE,
+            address: 0x200
+        }]
+    );
+}
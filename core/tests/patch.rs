@@ -0,0 +1,212 @@
+use chippers_core::{
+    error::PatchError,
+    patch::{apply_bps, apply_ips, apply_patch, create_bps, create_ips},
+};
+use flate2::Crc;
+
+const ROM: [u8; 8] = [0x60, 0x05, 0xA0, 0x00, 0xD0, 0x05, 0x12, 0x06];
+
+#[test]
+fn create_then_apply() {
+    let mut modified = ROM.to_vec();
+    modified[1] = 0x0A;
+    modified[4..6].copy_from_slice(&[0xD1, 0x15]);
+    modified.extend_from_slice(&[0x00, 0xE0]);
+
+    let patch = create_ips(&ROM, &modified).unwrap();
+    #[rustfmt::skip]
+    assert_eq!(
+        patch,
+        [
+            b'P', b'A', b'T', b'C', b'H',
+            0x00, 0x00, 0x01, 0x00, 0x01, 0x0A,
+            0x00, 0x00, 0x04, 0x00, 0x02, 0xD1, 0x15,
+            0x00, 0x00, 0x08, 0x00, 0x02, 0x00, 0xE0,
+            b'E', b'O', b'F',
+        ]
+    );
+    assert_eq!(apply_ips(&ROM, &patch).unwrap(), modified);
+}
+
+#[test]
+fn identical_roms_give_an_empty_patch() {
+    let patch = create_ips(&ROM, &ROM).unwrap();
+    assert_eq!(patch, b"PATCHEOF");
+    assert_eq!(apply_ips(&ROM, &patch).unwrap(), ROM);
+}
+
+#[test]
+fn run_length_encoded_records() {
+    // 4 bytes of 0xFF at offset 2, then 3 bytes of 0xAA that grow the ROM
+    #[rustfmt::skip]
+    let patch = [
+        b'P', b'A', b'T', b'C', b'H',
+        0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04, 0xFF,
+        0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x03, 0xAA,
+        b'E', b'O', b'F',
+    ];
+    assert_eq!(
+        apply_ips(&ROM, &patch).unwrap(),
+        [0x60, 0x05, 0xFF, 0xFF, 0xFF, 0xFF, 0x12, 0x06, 0x00, 0xAA, 0xAA, 0xAA]
+    );
+}
+
+#[test]
+fn truncation_extension() {
+    let patch = b"PATCHEOF\x00\x00\x04";
+    assert_eq!(apply_ips(&ROM, patch).unwrap(), ROM[..4]);
+
+    // Only a 3 byte size counts as the extension
+    let patch = b"PATCHEOF\x00\x04";
+    assert_eq!(apply_ips(&ROM, patch).unwrap(), ROM);
+}
+
+#[test]
+fn shrunk_rom() {
+    let mut modified = ROM[..6].to_vec();
+    modified[0] = 0x61;
+
+    let patch = create_ips(&ROM, &modified).unwrap();
+    assert!(patch.ends_with(b"EOF\x00\x00\x06"));
+    assert_eq!(apply_ips(&ROM, &patch).unwrap(), modified);
+
+    let patch = create_ips(&ROM, &ROM[..2]).unwrap();
+    assert_eq!(patch, b"PATCHEOF\x00\x00\x02");
+    assert_eq!(apply_ips(&ROM, &patch).unwrap(), ROM[..2]);
+}
+
+#[test]
+fn truncated_records() {
+    let truncated: [&[u8]; 5] = [
+        b"PATCH",
+        b"PATCH\x00\x00",
+        b"PATCH\x00\x00\x01\x00\x02\x0A",
+        b"PATCH\x00\x00\x01\x00\x00\x00\x04",
+        b"PATCH\x00\x00\x01\x00\x01\x0A",
+    ];
+    for patch in truncated {
+        assert!(
+            matches!(apply_ips(&ROM, patch), Err(PatchError::Truncated)),
+            "{patch:?}"
+        );
+    }
+}
+
+#[test]
+fn bad_header() {
+    for patch in [&b""[..], b"PATC", b"IPS32EOF", b"patchEOF"] {
+        assert!(
+            matches!(apply_ips(&ROM, patch), Err(PatchError::InvalidHeader)),
+            "{patch:?}"
+        );
+    }
+}
+
+#[test]
+fn footer_offset_limit() {
+    // A record can't start at 0x454F46, which reads as "EOF"
+    let largest = vec![0xFF; 0x454F46];
+    let patch = create_ips(&[], &largest).unwrap();
+    assert_eq!(apply_ips(&[], &patch).unwrap(), largest);
+
+    let too_large = vec![0xFF; 0x454F47];
+    assert!(matches!(
+        create_ips(&[], &too_large),
+        Err(PatchError::TooLarge { size: 0x454F47 })
+    ));
+}
+
+// Appends the source, target and patch CRC-32s that end a BPS patch
+fn bps(actions: &[u8], source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut patch = actions.to_vec();
+    for data in [source, target] {
+        let mut crc = Crc::new();
+        crc.update(data);
+        patch.extend_from_slice(&crc.sum().to_le_bytes());
+    }
+    let mut crc = Crc::new();
+    crc.update(&patch);
+    patch.extend_from_slice(&crc.sum().to_le_bytes());
+    patch
+}
+
+#[test]
+fn bps_actions() {
+    let target = [0xD0, 0x05, 0x60, 0x05, 0xAA, 0xAA, 0xAA, 0xAA];
+    #[rustfmt::skip]
+    let actions = [
+        b'B', b'P', b'S', b'1',
+        0x88, 0x88, 0x80, // source size, target size, no metadata
+        0x86, 0x88, // copy 2 bytes from source offset 4
+        0x86, 0x8D, // copy 2 bytes from source offset 0, 6 back
+        0x81, 0xAA, // 1 byte from the patch
+        0x8B, 0x88, // copy 3 bytes from target offset 4, overlapping the copy
+    ];
+    let patch = bps(&actions, &ROM, &target);
+    assert_eq!(apply_bps(&ROM, &patch).unwrap(), target);
+    assert_eq!(apply_patch(&ROM, &patch).unwrap(), target);
+}
+
+#[test]
+fn bps_create_then_apply() {
+    let mut grown = ROM.to_vec();
+    grown[1] = 0x0A;
+    grown.extend_from_slice(&[0x00, 0xE0]);
+
+    for modified in [&grown[..], &ROM[..3], &ROM, &[]] {
+        let patch = create_bps(&ROM, modified);
+        assert_eq!(apply_bps(&ROM, &patch).unwrap(), modified);
+    }
+}
+
+#[test]
+fn bps_checksums() {
+    let mut modified = ROM.to_vec();
+    modified[0] = 0x61;
+    let patch = create_bps(&ROM, &modified);
+
+    assert!(matches!(
+        apply_bps(&modified, &patch),
+        Err(PatchError::SourceMismatch)
+    ));
+
+    let mut corrupted = patch.clone();
+    corrupted[8] ^= 1;
+    assert!(matches!(
+        apply_bps(&ROM, &corrupted),
+        Err(PatchError::ChecksumMismatch)
+    ));
+
+    // A patch whose actions don't produce the target it was made for
+    let wrong_target = bps(&patch[..patch.len() - 12], &ROM, &ROM);
+    assert!(matches!(
+        apply_bps(&ROM, &wrong_target),
+        Err(PatchError::TargetMismatch)
+    ));
+}
+
+#[test]
+fn bps_out_of_bounds_and_truncated() {
+    // Copies 2 bytes from source offset 7, past the end of the ROM
+    let patch = bps(b"BPS1\x88\x88\x80\x86\x8E", &ROM, &ROM);
+    assert!(matches!(
+        apply_bps(&ROM, &patch),
+        Err(PatchError::OutOfBounds)
+    ));
+
+    // Reads 2 bytes from the patch, which only holds 1
+    let patch = bps(b"BPS1\x88\x88\x80\x85\xAA", &ROM, &ROM);
+    assert!(matches!(
+        apply_bps(&ROM, &patch),
+        Err(PatchError::Truncated)
+    ));
+
+    assert!(matches!(
+        apply_bps(&ROM, b"BPS1"),
+        Err(PatchError::Truncated)
+    ));
+    assert!(matches!(
+        apply_patch(&ROM, b"UPS1"),
+        Err(PatchError::InvalidHeader)
+    ));
+}
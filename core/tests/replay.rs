@@ -0,0 +1,106 @@
+use chippers_core::{
+    error::ReplayError,
+    interpreter::{Key, KeyEvent, Quirks},
+    replay::{Divergence, Movie},
+};
+
+#[test]
+fn replay_reproduces_run() {
+    // Draws a random digit wherever a held key puts it, forever
+    let program = [
+        0xC0, 0x0F, 0xF0, 0x29, 0x61, 0x00, 0xE1, 0xA1, 0x71, 0x08, 0xD1, 0x15, 0x12, 0x00,
+    ];
+    let mut movie = Movie::new(42, 8, Quirks::MODERN);
+    let mut recording = movie.start(&program).unwrap();
+    for frame in 0..30 {
+        if frame % 7 == 0 {
+            let event = KeyEvent {
+                key: Key::Key0,
+                pressed: frame % 14 == 0,
+                frame: 0,
+            };
+            movie.record(&mut recording, event);
+        }
+        recording.run_frame(8).unwrap();
+    }
+    assert_eq!(movie.events.len(), 5);
+
+    let mut playback = movie.play(&program).unwrap();
+    for _ in 0..30 {
+        playback.run_frame(8).unwrap();
+    }
+    assert_eq!(playback.state_hash(), recording.state_hash());
+}
+
+#[test]
+fn checksums_catch_desyncs() {
+    // Draws a random digit, forever
+    let program = [0xC0, 0x0F, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x00];
+    let mut movie = Movie::new(42, 8, Quirks::MODERN);
+    movie.checksum_interval = 5;
+    let mut recording = movie.start(&program).unwrap();
+    for _ in 0..12 {
+        recording.run_frame(8).unwrap();
+        movie.checkpoint(&recording);
+    }
+    let frames: Vec<u64> = movie.checksums.iter().map(|&(frame, _)| frame).collect();
+    assert_eq!(frames, [5, 10]);
+
+    let mut playback = movie.play(&program).unwrap();
+    for _ in 0..12 {
+        playback.run_frame(8).unwrap();
+        movie.verify(&playback).unwrap();
+    }
+
+    // Another seed draws other digits, caught at the next checksum
+    let desynced = Movie { seed: 7, ..movie };
+    let mut playback = desynced.play(&program).unwrap();
+    let error = (0..12)
+        .find_map(|_| {
+            playback.run_frame(8).unwrap();
+            desynced.verify(&playback).err()
+        })
+        .unwrap();
+    assert_eq!(error, ReplayError::Desync { frame: 5 });
+}
+
+#[test]
+fn compare_finds_first_divergent_instruction() {
+    let program = [
+        0x60, 0x01, // LD V0, 0x01
+        0x61, 0x04, // LD V1, 0x04
+        0x80, 0x16, // SHR V0, V1, which reads V1 on the COSMAC VIP
+        0x12, 0x06, // JP 0x206
+    ];
+    let movie = Movie::new(0, 2, Quirks::COSMAC);
+
+    assert_eq!(movie.compare(&program, Quirks::COSMAC, 60).unwrap(), None);
+    assert_eq!(
+        movie.compare(&program, Quirks::MODERN, 60).unwrap(),
+        Some(Divergence {
+            frame: 1,
+            instruction: Some((0x204, 0x8016)),
+        })
+    );
+}
+
+#[test]
+fn compare_counts_errors_in_one_run() {
+    // Reads 2 bytes at 0xFFF, which only wraps around with `wrap_memory`
+    let program = [0xAF, 0xFF, 0xF1, 0x65, 0x12, 0x04];
+    let wrapping = Quirks {
+        wrap_memory: true,
+        ..Quirks::MODERN
+    };
+    let movie = Movie::new(0, 4, Quirks::MODERN);
+
+    assert_eq!(
+        movie.compare(&program, wrapping, 60).unwrap(),
+        Some(Divergence {
+            frame: 0,
+            instruction: Some((0x202, 0xF165)),
+        })
+    );
+    // The same error in both runs isn't a difference
+    assert_eq!(movie.compare(&program, Quirks::MODERN, 60).unwrap(), None);
+}
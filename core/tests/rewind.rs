@@ -0,0 +1,47 @@
+use chippers_core::{
+    interpreter::{Interpreter, Key},
+    rewind::Rewind,
+};
+
+#[test]
+fn rewind_steps_back_through_snapshots() {
+    // Counts in V0 forever
+    let mut interpreter = Interpreter::new(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    let mut rewind = Rewind::new(2, 3);
+    rewind.record(&interpreter);
+    for _ in 0..10 {
+        interpreter.run_frame(2).unwrap();
+        rewind.record(&interpreter);
+    }
+    // Snapshots at frames 6, 8 and 10 are left
+    assert_eq!(rewind.len(), 3);
+
+    assert!(rewind.step_back(&mut interpreter));
+    assert_eq!(interpreter.frames(), 8);
+    assert!(rewind.step_back(&mut interpreter));
+    assert_eq!(interpreter.frames(), 6);
+    assert_eq!(interpreter.variable_registers[0], 6);
+    assert!(!rewind.step_back(&mut interpreter));
+}
+
+#[test]
+fn rewind_restores_random_generator_and_keys() {
+    // Draws a random byte into V0 forever
+    let mut interpreter = Interpreter::builder()
+        .deterministic(7, 10)
+        .build(&[0xC0, 0xFF, 0x12, 0x00])
+        .unwrap();
+    interpreter.input_handler.press(Key::from(5));
+    let mut rewind = Rewind::new(1, 4);
+    interpreter.run_frame(10).unwrap();
+    rewind.record(&interpreter);
+
+    interpreter.run_frame(10).unwrap();
+    let expected = interpreter.variable_registers[0];
+    interpreter.run_frame(10).unwrap();
+
+    assert!(rewind.step_back(&mut interpreter));
+    assert!(interpreter.input_handler.is_pressed(Key::from(5)));
+    interpreter.run_frame(10).unwrap();
+    assert_eq!(interpreter.variable_registers[0], expected);
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use chippers_core::{
+    core::Banks,
+    error::{LoadError, SaveStateError},
+    interpreter::{Interpreter, Quirks},
+};
+use flate2::Crc;
+
+use common::{IDLE, SCREEN_HASH};
+
+#[test]
+fn state_hash() {
+    let program = [0x60, 0x05, 0xA3, 0x00, 0xD0, 0x05];
+    let mut first = Interpreter::new(&program).unwrap();
+    let mut second = Interpreter::new(&program).unwrap();
+    assert_eq!(first.state_hash(), second.state_hash());
+
+    first.run_frame(3).unwrap();
+    assert_ne!(first.state_hash(), second.state_hash());
+    second.run_frame(3).unwrap();
+    assert_eq!(first.state_hash(), second.state_hash());
+    assert_eq!(first.screen.hash(), second.screen.hash());
+
+    // The value is fixed, not just consistent within a run
+    assert_eq!(Interpreter::new(&IDLE).unwrap().screen.hash(), SCREEN_HASH);
+}
+
+#[test]
+fn cloned_interpreter_runs_identically() {
+    // Draws random bytes forever
+    let program = [0xC0, 0xFF, 0xA0, 0x00, 0xD0, 0x01, 0x12, 0x00];
+    let mut original = Interpreter::builder()
+        .deterministic(7, 10)
+        .build(&program)
+        .unwrap();
+    original.run_frame(10).unwrap();
+
+    let mut clone = original.clone();
+    assert_eq!(clone, original);
+    original.run_frame(10).unwrap();
+    clone.run_frame(10).unwrap();
+    assert_eq!(clone, original);
+
+    let debug = format!("{original:?}");
+    assert!(debug.starts_with("Interpreter { status: Running"));
+    assert!(debug.len() < 2000);
+}
+
+#[cfg(feature = "schip")]
+#[test]
+fn cloned_interpreter_keeps_flags() {
+    use std::sync::{Arc, Mutex};
+
+    use chippers_core::flags::FlagStorage;
+
+    struct SharedFlags(Arc<Mutex<[u8; 16]>>);
+
+    impl FlagStorage for SharedFlags {
+        fn load(&mut self) -> [u8; 16] {
+            *self.0.lock().unwrap()
+        }
+
+        fn save(&mut self, flags: &[u8; 16]) {
+            *self.0.lock().unwrap() = *flags;
+        }
+    }
+
+    let saved = Arc::new(Mutex::new([0; 16]));
+    let mut original = Interpreter::new(&[0xF1, 0x75, 0xF1, 0x85, 0x12, 0x04]).unwrap();
+    original.set_flag_storage(SharedFlags(saved.clone()));
+    original.variable_registers[0] = 0xAB;
+    original.variable_registers[1] = 0xCD;
+    original.step().unwrap();
+
+    let mut clone = original.clone();
+    assert_eq!(clone, original);
+    clone.variable_registers[0] = 0;
+    clone.variable_registers[1] = 0;
+    clone.step().unwrap();
+    assert_eq!(clone.variable_registers[0], 0xAB);
+    assert_eq!(clone.variable_registers[1], 0xCD);
+
+    // The clone's flags live in memory, the original's storage isn't shared
+    let mut clone = clone.clone();
+    clone.variable_registers[0] = 0x11;
+    clone.program_counter = 0x200;
+    clone.step().unwrap();
+    assert_ne!(clone, original);
+    assert_eq!(saved.lock().unwrap()[..2], [0xAB, 0xCD]);
+}
+
+#[test]
+fn equality_covers_errors_but_not_configuration() {
+    let mut first = Interpreter::new(&[0x00, 0xEE]).unwrap();
+    let mut second = first.clone();
+    second.quirks = Quirks::COSMAC;
+    assert_eq!(first, second);
+
+    assert!(first.step().is_err());
+    assert_ne!(first, second);
+    assert!(second.step().is_err());
+    assert_eq!(first, second);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let program = [0x60, 0x05, 0xF0, 0x29, 0x00, 0xFF, 0xD0, 0x05, 0x12, 0x08];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.run_frame(4).unwrap();
+
+    let json = serde_json::to_string(&interpreter).unwrap();
+    let restored: Interpreter = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, interpreter);
+    assert_eq!(restored.state_hash(), interpreter.state_hash());
+}
+
+#[test]
+fn save_state_round_trip() {
+    // Draws digits while counting in V0
+    let program = [
+        0xF0, 0x29, 0xD1, 0x15, 0x70, 0x01, 0x22, 0x0A, 0x12, 0x00, 0x00, 0xEE,
+    ];
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.run_frame(10).unwrap();
+    let saved = interpreter.clone();
+    let state = interpreter.save_state();
+
+    interpreter.run_frame(10).unwrap();
+    assert_ne!(interpreter, saved);
+    interpreter.load_state(&state).unwrap();
+    assert_eq!(interpreter, saved);
+
+    // Damaged states are rejected without touching the interpreter
+    let mut corrupted = state.clone();
+    corrupted[100] ^= 1;
+    assert!(matches!(
+        interpreter.load_state(&corrupted),
+        Err(SaveStateError::ChecksumMismatch)
+    ));
+    assert!(matches!(
+        interpreter.load_state(&program),
+        Err(SaveStateError::InvalidMagic)
+    ));
+    assert_eq!(interpreter, saved);
+}
+
+#[test]
+fn migrate_state() {
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    interpreter.run_frame(10).unwrap();
+    let state = interpreter.save_state();
+
+    // A state in the current version comes out unchanged
+    assert_eq!(Interpreter::migrate_state(&state).unwrap(), state);
+
+    // Unknown versions are reported rather than guessed at
+    let mut future = state.clone();
+    future[4..6].copy_from_slice(&99u16.to_le_bytes());
+    let end = future.len() - 4;
+    let mut crc = Crc::new();
+    crc.update(&future[..end]);
+    future[end..].copy_from_slice(&crc.sum().to_le_bytes());
+    assert!(matches!(
+        Interpreter::migrate_state(&future),
+        Err(SaveStateError::UnsupportedVersion { version: 99 })
+    ));
+    assert!(matches!(
+        Interpreter::migrate_state(&IDLE),
+        Err(SaveStateError::InvalidMagic)
+    ));
+}
+
+#[test]
+fn save_state_with_banks_out_of_memory_is_rejected() {
+    let mut interpreter = Interpreter::new(&IDLE).unwrap();
+    let banks = Banks::new(0x800, vec![vec![1; 4], vec![2; 4]]).unwrap();
+    interpreter.set_banks(banks).unwrap();
+    let mut state = interpreter.save_state();
+
+    // Moves the window, which is followed by the selection, bank count and both
+    // banks, then the checksum, so that it ends past the end of memory
+    let window = state.len() - 4 - 2 * 8 - 4 - 4 - 2;
+    state[window..window + 2].copy_from_slice(&0xFFEu16.to_le_bytes());
+    let end = state.len() - 4;
+    let mut crc = Crc::new();
+    crc.update(&state[..end]);
+    state[end..].copy_from_slice(&crc.sum().to_le_bytes());
+
+    let mut restored = Interpreter::new(&IDLE).unwrap();
+    assert!(matches!(
+        restored.load_state(&state),
+        Err(SaveStateError::Load(LoadError::SegmentOutOfBounds {
+            address: 0xFFE,
+            size: 4
+        }))
+    ));
+}
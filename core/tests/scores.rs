@@ -0,0 +1,136 @@
+mod common;
+
+use chippers_core::{
+    error::ScoreLocationError,
+    scores::{HighScores, ScoreEncoding, ScoreLocation},
+};
+
+use common::given;
+
+fn location(address: u16, length: u16, encoding: ScoreEncoding) -> ScoreLocation {
+    ScoreLocation {
+        address,
+        length,
+        encoding,
+    }
+}
+
+#[test]
+fn read_scores() {
+    let ram = given()
+        .mem(0x300, &[1, 2, 3])
+        .mem(0x310, &[0x12, 0x34])
+        .mem(0x320, &[0x01, 0x02])
+        .interpreter
+        .ram;
+
+    assert_eq!(
+        location(0x300, 3, ScoreEncoding::UnpackedBcd).read(&ram),
+        Some(123)
+    );
+    assert_eq!(
+        location(0x310, 2, ScoreEncoding::PackedBcd).read(&ram),
+        Some(1234)
+    );
+    assert_eq!(
+        location(0x320, 2, ScoreEncoding::Binary).read(&ram),
+        Some(258)
+    );
+}
+
+#[test]
+fn invalid_scores_read_as_none() {
+    let ram = given()
+        .mem(0x300, &[1, 10])
+        .mem(0x310, &[0x1A])
+        .interpreter
+        .ram;
+
+    assert_eq!(
+        location(0x300, 2, ScoreEncoding::UnpackedBcd).read(&ram),
+        None
+    );
+    assert_eq!(
+        location(0x310, 1, ScoreEncoding::PackedBcd).read(&ram),
+        None
+    );
+    // Out of bounds, and too large for a u32
+    assert_eq!(location(0xFFF, 2, ScoreEncoding::Binary).read(&ram), None);
+    let ram = given().mem(0x300, &[0xFF; 5]).interpreter.ram;
+    assert_eq!(location(0x300, 5, ScoreEncoding::Binary).read(&ram), None);
+}
+
+#[test]
+fn parse_score_location() {
+    assert_eq!(
+        "0x2F0:3:bcd".parse(),
+        Ok(location(0x2F0, 3, ScoreEncoding::UnpackedBcd))
+    );
+    assert_eq!(
+        "2f0:2:packed".parse(),
+        Ok(location(0x2F0, 2, ScoreEncoding::PackedBcd))
+    );
+    assert_eq!(
+        "0x300:1:binary".parse(),
+        Ok(location(0x300, 1, ScoreEncoding::Binary))
+    );
+
+    for invalid in ["", "0x2F0:3", "0x2F0:0:bcd", "0xZZZ:3:bcd", "0x2F0:3:bcd:1"] {
+        assert_eq!(
+            invalid.parse::<ScoreLocation>(),
+            Err(ScoreLocationError::InvalidFormat),
+            "{invalid}"
+        );
+    }
+    assert_eq!(
+        "0x2F0:3:hex".parse::<ScoreLocation>(),
+        Err(ScoreLocationError::UnknownEncoding("hex".into()))
+    );
+}
+
+#[test]
+fn submit_scores() {
+    let mut table = HighScores::new(3);
+    assert_eq!(table.submit(10), Some(0));
+    assert_eq!(table.submit(30), Some(0));
+    // Ties rank below existing scores
+    assert_eq!(table.submit(10), Some(2));
+    assert_eq!(table.scores(), [30, 10, 10]);
+
+    assert_eq!(table.submit(5), None);
+    assert_eq!(table.submit(20), Some(1));
+    assert_eq!(table.scores(), [30, 20, 10]);
+}
+
+#[test]
+fn restored_tables_are_sorted_and_truncated() {
+    let table = HighScores::from_scores(vec![5, 50, 20, 40], 3);
+    assert_eq!(table.scores(), [50, 40, 20]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn save_and_load_table() {
+    use std::fs;
+
+    let directory = std::env::temp_dir().join("chippers_scores");
+    fs::create_dir_all(&directory).unwrap();
+    let path = HighScores::path(&directory, &[0x12, 0x00]);
+    assert_eq!(path, directory.join("392d622c.scores"));
+    let _ = fs::remove_file(&path);
+
+    // A missing file is an empty table
+    assert!(HighScores::load(&path, 5).scores().is_empty());
+
+    let mut table = HighScores::new(5);
+    for score in [100, 300, 200] {
+        table.submit(score);
+    }
+    table.save(&path).unwrap();
+    assert_eq!(HighScores::load(&path, 5).scores(), [300, 200, 100]);
+    assert_eq!(HighScores::load(&path, 2).scores(), [300, 200]);
+
+    // Lines that aren't scores are skipped
+    fs::write(&path, "70\nnot a score\n\n90\n").unwrap();
+    assert_eq!(HighScores::load(&path, 5).scores(), [90, 70]);
+}
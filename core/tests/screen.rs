@@ -0,0 +1,151 @@
+mod common;
+
+use chippers_core::{
+    core::{Glyphs, Resolution, ScreenFormat},
+    interpreter::Interpreter,
+};
+
+use common::given;
+
+#[test]
+fn changed_rows() {
+    let state = given().i(0x300).mem(0x300, &[0xFF, 0x00, 0xFF]);
+    let before = state.interpreter.screen.clone();
+
+    // Row 5 is drawn blank, only rows 4 and 6 light up
+    let state = state.reg(0, 4).exec(0xD003);
+    assert_eq!(state.interpreter.screen.changed_rows(&before), 0b101 << 4);
+    assert_eq!(
+        state
+            .interpreter
+            .screen
+            .changed_rows(&state.interpreter.screen),
+        0
+    );
+}
+
+#[test]
+fn text_display() {
+    let state = given()
+        .i(0x300)
+        .mem(0x300, &[0xC0, 0x40])
+        .reg(0, 62)
+        .reg(1, 30)
+        .exec(0xD012);
+    let format = ScreenFormat {
+        glyphs: Glyphs::HashDot,
+        double_width: false,
+        border: false,
+    };
+    let text = state.interpreter.screen.display(format).to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert_eq!(lines[29], ".".repeat(64));
+    assert_eq!(lines[30], format!("{}##", ".".repeat(62)));
+    assert_eq!(lines[31], format!("{}.#", ".".repeat(62)));
+
+    // Two rows per line, with a border
+    let compact = state.interpreter.screen.compact().to_string();
+    let lines: Vec<&str> = compact.lines().collect();
+    assert_eq!(lines.len(), 16 + 2);
+    assert_eq!(lines[0], "-".repeat(66));
+    assert_eq!(lines[16], format!("|{}▀█|", " ".repeat(62)));
+}
+
+#[test]
+fn pixels_outside_the_resolution_are_off() {
+    let state = given().i(0x300).mem(0x300, &[0xFF]).reg(0, 56).exec(0xD011);
+    let screen = &state.interpreter.screen;
+    assert!(screen.get(63, 0));
+
+    // The low resolution only uses the first 64 columns and 32 rows
+    for (x, y) in [(64, 0), (127, 0), (200, 0), (0, 32), (0, 64), (0, 1000)] {
+        assert!(!screen.get(x, y), "({x}, {y})");
+        assert_eq!(screen.color_index(x, y), 0, "({x}, {y})");
+    }
+}
+
+#[test]
+fn two_page_mode() {
+    let mut program = vec![0; 0xC0];
+    program[..2].copy_from_slice(&[0x12, 0x60]);
+    program.extend_from_slice(&[
+        0x60, 0x00, // LD V0, 0x00
+        0x61, 0x28, // LD V1, 0x28
+        0xA2, 0xCC, // LD I, 0x2CC
+        0xD0, 0x11, // DRW V0, V1, 1
+        0x02, 0x30, // clears the 64x64 screen
+        0x12, 0xCA, // JP 0x2CA
+        0x80, // sprite
+    ]);
+
+    // The 1260 entry is recognized, and the program starts past the 1802 code
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    assert_eq!(interpreter.screen.resolution(), Resolution::TwoPage);
+    assert_eq!(
+        (interpreter.screen.width(), interpreter.screen.height()),
+        (64, 64)
+    );
+    assert_eq!(interpreter.program_counter, 0x2C0);
+
+    for _ in 0..4 {
+        interpreter.step().unwrap();
+    }
+    assert!(interpreter.screen.get(0, 40));
+    let state = interpreter.save_state();
+    assert!(interpreter.step().unwrap().screen_changed);
+    assert!(!interpreter.screen.get(0, 40));
+
+    interpreter.load_state(&state).unwrap();
+    assert_eq!(interpreter.screen.resolution(), Resolution::TwoPage);
+    assert!(interpreter.screen.get(0, 40));
+
+    // Other programs keep the 64x32 screen
+    interpreter.load_new_rom(&program[0xC0..]).unwrap();
+    assert_eq!(interpreter.screen.resolution(), Resolution::Low);
+    interpreter.load_new_rom(&program).unwrap();
+    assert_eq!(interpreter.program_counter, 0x2C0);
+}
+
+#[test]
+fn stack_display() {
+    let state = given().exec(0x2300).exec(0x2400);
+    assert_eq!(state.interpreter.stack.to_string(), "[0x202, 0x302]");
+    assert_eq!(state.interpreter.stack.depth(), 2);
+}
+
+#[cfg(feature = "xochip")]
+#[test]
+fn rgba_uses_both_planes() {
+    use chippers_core::core::Palette;
+
+    let state = given()
+        .mem(0x300, &[0b1100_0000, 0b1010_0000])
+        .i(0x300)
+        .exec(0xF301)
+        .exec(0xD001);
+    let palette = Palette([[0; 4], [1; 4], [2; 4], [3; 4]]);
+    let rgba = state.interpreter.screen.to_rgba(&palette);
+    assert_eq!(rgba.len(), 64 * 32 * 4);
+    assert_eq!(rgba[..16], [3, 3, 3, 3, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0]);
+    assert!(rgba[16..].iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn set_resolution() {
+    let mut screen = given()
+        .mem(0x300, &[0xFF])
+        .i(0x300)
+        .exec(0xD001)
+        .interpreter
+        .screen;
+    assert_eq!(screen.resolution(), Resolution::Low);
+
+    screen.set_resolution(Resolution::High);
+    assert_eq!((screen.width(), screen.height()), (128, 64));
+    assert!(screen.rows().all(|row| row == 0));
+
+    screen.set_resolution(Resolution::Low);
+    assert_eq!((screen.width(), screen.height()), (64, 32));
+    assert!(!screen.is_high_resolution());
+}
@@ -1,31 +1,123 @@
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle, window::WindowMode};
+use std::path::Path;
+
+use bevy::{
+    input::keyboard::KeyboardInput,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    window::WindowMode,
+};
+use chippers_core::{
+    archive::read_roms, core::Palette, interpreter::Interpreter, keymap::scancode_key,
+};
+
+// Texture the interpreter's screen is copied into every frame
+#[derive(Resource)]
+struct ScreenTexture(Handle<Image>);
 
 fn main() {
+    let rom_path = match std::env::args().skip(1).collect::<Vec<_>>().as_slice() {
+        [rom_path] => rom_path.clone(),
+        _ => {
+            eprintln!("usage: chippers_bevy_frontend <rom>");
+            std::process::exit(1);
+        }
+    };
+    let mut roms = read_roms(Path::new(&rom_path)).expect("could not read ROM");
+    if roms.is_empty() {
+        eprintln!("no .ch8 file in {rom_path}");
+        std::process::exit(1);
+    }
+    let interpreter = Interpreter::new(&roms.swap_remove(0).data).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+
     App::new()
         .insert_resource(ClearColor(Color::hex("58505D").unwrap()))
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Chippers (CHIP-8)".into(),
-                mode: WindowMode::Fullscreen,
-                ..default()
-            }),
-            ..default()
-        }))
+        // The interpreter's callbacks and random generator aren't Sync
+        .insert_non_send_resource(interpreter)
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Chippers (CHIP-8)".into(),
+                        mode: WindowMode::Fullscreen,
+                        ..default()
+                    }),
+                    ..default()
+                })
+                .set(ImagePlugin::default_nearest()),
+        )
         .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            (update_keypad, run_interpreter, update_screen).chain(),
+        )
         .run();
 }
 
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
     let screen_width = 1920.;
+    let image = Image::new_fill(
+        Extent3d {
+            width: 64,
+            height: 32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0xFF],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    let texture = images.add(image);
+
     commands.spawn(Camera2dBundle::default());
-    commands.spawn(MaterialMesh2dBundle {
-        mesh: meshes.add(Mesh::from(shape::Quad::default())).into(),
-        transform: Transform::default().with_scale(Vec3::new(screen_width, screen_width / 2., 0.)),
-        material: materials.add(ColorMaterial::from(Color::DARK_GREEN)),
+    commands.spawn(SpriteBundle {
+        texture: texture.clone(),
+        sprite: Sprite {
+            custom_size: Some(Vec2::new(screen_width, screen_width / 2.)),
+            ..default()
+        },
         ..default()
     });
+    commands.insert_resource(ScreenTexture(texture));
+}
+
+// Keys are mapped by physical position, so the keypad keeps its shape on any layout.
+// Scan codes are PC set 1 codes on Windows and Linux, macOS reports its own codes.
+fn update_keypad(mut interpreter: NonSendMut<Interpreter>, mut events: EventReader<KeyboardInput>) {
+    for event in events.iter() {
+        if let Some(key) = scancode_key(event.scan_code) {
+            interpreter.input_handler.set(key, event.state.is_pressed());
+        }
+    }
+}
+
+fn run_interpreter(mut interpreter: NonSendMut<Interpreter>, time: Res<Time>) {
+    if let Err(error) = interpreter.run_for(time.delta()) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+fn update_screen(
+    interpreter: NonSend<Interpreter>,
+    texture: Res<ScreenTexture>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(image) = images.get_mut(&texture.0) else {
+        return;
+    };
+
+    // The texture follows resolution switches
+    let size = Extent3d {
+        width: interpreter.screen.width() as u32,
+        height: interpreter.screen.height() as u32,
+        depth_or_array_layers: 1,
+    };
+    if image.texture_descriptor.size != size {
+        image.resize(size);
+    }
+    interpreter
+        .screen
+        .write_rgba(&Palette::default(), &mut image.data);
 }
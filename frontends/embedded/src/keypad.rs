@@ -0,0 +1,45 @@
+use chippers_core::keymap::KEYPAD;
+use embedded_hal::digital::{InputPin, OutputPin};
+
+#[derive(Debug)]
+pub enum KeypadError<R, C> {
+    Row(R),
+    Column(C),
+}
+
+/// A 4x4 key matrix with pulled-up row inputs and column outputs.
+pub struct MatrixKeypad<R, C> {
+    rows: [R; 4],
+    columns: [C; 4],
+}
+
+impl<R: InputPin, C: OutputPin> MatrixKeypad<R, C> {
+    /// Drives every column high, so the first scan doesn't see a column left low.
+    pub fn new(rows: [R; 4], mut columns: [C; 4]) -> Result<Self, C::Error> {
+        for column in &mut columns {
+            column.set_high()?;
+        }
+        Ok(Self { rows, columns })
+    }
+
+    /// Returns the pressed state of each key, indexed by key value.
+    pub fn scan(&mut self) -> Result<[bool; 16], KeypadError<R::Error, C::Error>> {
+        let mut pressed = [false; 16];
+
+        for (column_index, column) in self.columns.iter_mut().enumerate() {
+            // Drive a single column low, a pressed key pulls its row low
+            column.set_low().map_err(KeypadError::Column)?;
+
+            for (row_index, row) in self.rows.iter_mut().enumerate() {
+                if row.is_low().map_err(KeypadError::Row)? {
+                    let key = KEYPAD[row_index][column_index];
+                    pressed[u8::from(key) as usize] = true;
+                }
+            }
+
+            column.set_high().map_err(KeypadError::Column)?;
+        }
+
+        Ok(pressed)
+    }
+}
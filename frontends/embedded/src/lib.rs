@@ -0,0 +1,123 @@
+//! CHIP-8 frontend for embedded boards.
+//!
+//! Drives a `chippers_core` interpreter built without `std`, rendering to any
+//! `embedded-graphics` display and reading a 4x4 GPIO key matrix. The core still
+//! needs `alloc`, so the board must provide a global allocator.
+//!
+//! ```ignore
+//! let keypad = MatrixKeypad::new(rows, columns).unwrap();
+//! let mut board = Board::new(ROM, display, keypad, buzzer, 2).unwrap();
+//! loop {
+//!     board.run_frame(11).unwrap();
+//!     delay.delay_ms(16);
+//! }
+//! ```
+#![no_std]
+
+mod keypad;
+
+pub use keypad::{KeypadError, MatrixKeypad};
+
+use chippers_core::{
+    error::{InterpreterError, LoadError},
+    interpreter::{Interpreter, Key},
+};
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+
+#[derive(Debug)]
+pub enum Error<D, R, C, B> {
+    Interpreter(InterpreterError),
+    Display(D),
+    Keypad(KeypadError<R, C>),
+    Buzzer(B),
+}
+
+type BoardError<D, R, C, B> = Error<
+    <D as DrawTarget>::Error,
+    <R as ErrorType>::Error,
+    <C as ErrorType>::Error,
+    <B as ErrorType>::Error,
+>;
+
+pub struct Board<D, R, C, B> {
+    pub interpreter: Interpreter,
+    display: D,
+    keypad: MatrixKeypad<R, C>,
+    buzzer: B,
+    scale: u32,
+}
+
+impl<D, R, C, B> Board<D, R, C, B>
+where
+    D: DrawTarget,
+    D::Color: From<BinaryColor>,
+    R: InputPin,
+    C: OutputPin,
+    B: OutputPin,
+{
+    pub fn new(
+        program: &[u8],
+        display: D,
+        keypad: MatrixKeypad<R, C>,
+        buzzer: B,
+        scale: u32,
+    ) -> Result<Self, LoadError> {
+        Ok(Self {
+            interpreter: Interpreter::new(program)?,
+            display,
+            keypad,
+            buzzer,
+            scale,
+        })
+    }
+
+    /// Runs one 60 Hz frame: polls the keypad, executes the given number of
+    /// instructions, ticks the timers and presents the screen.
+    pub fn run_frame(
+        &mut self,
+        instructions_per_frame: usize,
+    ) -> Result<(), BoardError<D, R, C, B>> {
+        let keys = self.keypad.scan().map_err(Error::Keypad)?;
+        self.update_keys(keys);
+
+        self.interpreter
+            .run_frame(instructions_per_frame)
+            .map_err(Error::Interpreter)?;
+
+        if self.interpreter.sound_timer.value > 0 {
+            self.buzzer.set_high().map_err(Error::Buzzer)?;
+        } else {
+            self.buzzer.set_low().map_err(Error::Buzzer)?;
+        }
+
+        self.draw().map_err(Error::Display)
+    }
+
+    fn update_keys(&mut self, keys: [bool; 16]) {
+        for (index, pressed) in keys.into_iter().enumerate() {
+            self.interpreter
+                .input_handler
+                .set(Key::from(index as u8), pressed);
+        }
+    }
+
+    fn draw(&mut self) -> Result<(), D::Error> {
+        let scale = self.scale as usize;
+        let area = Rectangle::new(Point::zero(), Size::new(64 * self.scale, 32 * self.scale));
+        let screen = &self.interpreter.screen;
+        // The area always spans 64x32 scaled pixels, the 128x64 and 64x64 modes are
+        // sampled into it
+        let (columns, rows) = (64 * scale, 32 * scale);
+        let (width, height) = (screen.width(), screen.height());
+
+        // Stream the scaled screen row by row
+        let colors = (0..rows).flat_map(move |y| {
+            (0..columns).map(move |x| {
+                BinaryColor::from(screen.get(x * width / columns, y * height / rows)).into()
+            })
+        });
+
+        self.display.fill_contiguous(&area, colors)
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chippers_core::{
+    archive::{read_roms, RomFile},
+    battery::BatteryRange,
+    core::{Ram, Screen},
+    interpreter::{Interpreter, Key, KeyEvent},
+    patch::apply_patch,
+    scores::{HighScores, ScoreLocation},
+};
+use tungstenite::{Error, Message, WebSocket};
+
+const VIEWER: &str = include_str!("viewer.html");
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+const HIGH_SCORES: usize = 10;
+// Sent by the viewer in place of a keypad key while fast-forward is held
+const FAST_FORWARD: u8 = 0x10;
+
+// High-score table shared by every session of the served ROM
+struct Scoreboard {
+    location: ScoreLocation,
+    path: PathBuf,
+    table: Mutex<HighScores>,
+}
+
+impl Scoreboard {
+    // Reads the final score of a session and saves the table if it placed
+    fn record(&self, ram: &Ram) {
+        let Some(score) = self.location.read(ram) else {
+            return;
+        };
+        let mut table = self.table.lock().unwrap();
+        match table.submit(score) {
+            Some(rank) => {
+                println!("Session ended with {score} points, rank {}", rank + 1);
+                if let Err(error) = table.save(&self.path) {
+                    eprintln!("could not save high scores: {error}");
+                }
+            }
+            None => println!("Session ended with {score} points"),
+        }
+    }
+}
+
+// Memory range every session of the served ROM restores at start and saves at the end
+struct Battery {
+    range: BatteryRange,
+    path: PathBuf,
+}
+
+// How each viewer's session runs
+#[derive(Clone, Copy)]
+struct SessionConfig {
+    // Frames run per streamed frame while the viewer holds fast-forward
+    fast_forward_frames: u32,
+    // Print how long key events took to reach the screen when the session ends
+    report_latency: bool,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((rom_path, mut options)) = args.split_first() else {
+        usage()
+    };
+    let mut address = "0.0.0.0:8080";
+    if let [first, rest @ ..] = options {
+        if !first.starts_with("--") {
+            address = first;
+            options = rest;
+        }
+    }
+    let mut patch_path = None;
+    let mut score_location = None;
+    let mut scores_directory = PathBuf::from(".");
+    let mut battery_range = None;
+    let mut saves_directory = PathBuf::from(".");
+    let mut session = SessionConfig {
+        fast_forward_frames: 8,
+        report_latency: false,
+    };
+    let mut options = options.iter();
+    while let Some(flag) = options.next() {
+        // The only flag without a value
+        if flag == "--latency" {
+            session.report_latency = true;
+            continue;
+        }
+        let Some(value) = options.next() else { usage() };
+        match flag.as_str() {
+            "--patch" => patch_path = Some(value),
+            "--score" => {
+                score_location = Some(value.parse().unwrap_or_else(|error| {
+                    eprintln!("{error}");
+                    std::process::exit(1);
+                }))
+            }
+            "--scores" => scores_directory = value.into(),
+            "--battery" => {
+                battery_range = Some(value.parse().unwrap_or_else(|error| {
+                    eprintln!("{error}");
+                    std::process::exit(1);
+                }))
+            }
+            "--saves" => saves_directory = value.into(),
+            "--fast-forward" => {
+                session.fast_forward_frames = value.parse().unwrap_or_else(|_| usage())
+            }
+            _ => usage(),
+        }
+    }
+
+    let mut roms = read_roms(Path::new(rom_path)).expect("could not read ROM");
+    let rom = match roms.len() {
+        0 => {
+            eprintln!("no .ch8 file in {rom_path}");
+            std::process::exit(1);
+        }
+        1 => roms.remove(0),
+        _ => roms.swap_remove(choose_rom(&roms)),
+    };
+    let mut program = rom.data;
+    if let Some(patch_path) = patch_path {
+        let patch = std::fs::read(patch_path).expect("could not read patch");
+        program = apply_patch(&program, &patch).unwrap_or_else(|error| {
+            eprintln!("{error}");
+            std::process::exit(1);
+        });
+    }
+    if let Err(error) = Interpreter::new(&program) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+    let scoreboard = score_location.map(|location| {
+        let path = HighScores::path(&scores_directory, &program);
+        let table = HighScores::load(&path, HIGH_SCORES);
+        println!("High scores: {:?}", table.scores());
+        Arc::new(Scoreboard {
+            location,
+            path,
+            table: Mutex::new(table),
+        })
+    });
+    let battery = battery_range.map(|range| {
+        Arc::new(Battery {
+            range,
+            path: BatteryRange::path(&saves_directory, &program),
+        })
+    });
+    let listener = TcpListener::bind(address).expect("could not bind address");
+    println!("Serving {} on http://{address}", rom.name);
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let program = program.clone();
+        let scoreboard = scoreboard.clone();
+        let battery = battery.clone();
+        thread::spawn(move || {
+            let result = handle_connection(
+                stream,
+                &program,
+                scoreboard.as_deref(),
+                battery.as_deref(),
+                session,
+            );
+            if let Err(error) = result {
+                eprintln!("connection closed: {error}");
+            }
+        });
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: chippers_stream_frontend <rom> [address] [--patch <ips|bps>] [--score <address:length:bcd|packed|binary> [--scores <directory>]] [--battery <address:length> [--saves <directory>]] [--fast-forward <frames>] [--latency]"
+    );
+    std::process::exit(1);
+}
+
+// Asks on stdin which of the archive's ROMs to serve
+fn choose_rom(roms: &[RomFile]) -> usize {
+    for (index, rom) in roms.iter().enumerate() {
+        println!("{:>3}: {}", index + 1, rom.name);
+    }
+
+    loop {
+        print!("ROM to serve: ");
+        io::stdout().flush().expect("could not write to stdout");
+
+        let mut choice = String::new();
+        if io::stdin()
+            .read_line(&mut choice)
+            .expect("could not read stdin")
+            == 0
+        {
+            std::process::exit(1);
+        }
+        match choice.trim().parse::<usize>() {
+            Ok(choice @ 1..) if choice <= roms.len() => return choice - 1,
+            _ => println!("enter a number between 1 and {}", roms.len()),
+        }
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    program: &[u8],
+    scoreboard: Option<&Scoreboard>,
+    battery: Option<&Battery>,
+    session: SessionConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Peek at the request line to tell the viewer page from the socket upgrade
+    let mut buffer = [0; 1024];
+    let length = stream.peek(&mut buffer)?;
+
+    if buffer[..length].starts_with(b"GET /ws ") {
+        let socket = tungstenite::accept(stream)?;
+        let mut interpreter = Interpreter::new(program)?;
+        if let Some(battery) = battery {
+            battery.range.load(&battery.path, &mut interpreter)?;
+        }
+        let mut latencies = Vec::new();
+        let result = run_session(socket, &mut interpreter, session, &mut latencies);
+        // However the session ended, its score counts and its memory is kept
+        if let Some(scoreboard) = scoreboard {
+            scoreboard.record(&interpreter.ram);
+        }
+        if let Some(battery) = battery {
+            if let Err(error) = battery.range.save(&battery.path, &interpreter.ram) {
+                eprintln!("could not save battery memory: {error}");
+            }
+        }
+        if session.report_latency {
+            report_latency(&mut latencies);
+        }
+        result
+    } else {
+        let _ = stream.read(&mut buffer)?;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{VIEWER}",
+            VIEWER.len()
+        )?;
+        Ok(())
+    }
+}
+
+// Each viewer gets its own interpreter, paced at 60 frames per second
+fn run_session(
+    mut socket: WebSocket<TcpStream>,
+    interpreter: &mut Interpreter,
+    session: SessionConfig,
+    latencies: &mut Vec<(Duration, u64)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    socket.get_mut().set_nonblocking(true)?;
+
+    interpreter.idle_loop_skipping = true;
+    interpreter.record_key_latency = session.report_latency;
+    // When each key event still in the queue was received
+    let mut received = VecDeque::new();
+    let mut last_frame = Vec::new();
+    let mut next_frame = Instant::now();
+    let mut fast_forward = false;
+
+    loop {
+        // Drain pending key events, each one is a [key, pressed] pair
+        loop {
+            match socket.read() {
+                Ok(Message::Binary(event)) => match event[..] {
+                    [key @ 0..=0xF, pressed] => {
+                        interpreter.queue_key_event(KeyEvent {
+                            key: Key::from(key),
+                            pressed: pressed != 0,
+                            frame: interpreter.frames(),
+                        });
+                        if session.report_latency {
+                            received.push_back(Instant::now());
+                        }
+                    }
+                    [FAST_FORWARD, pressed] => fast_forward = pressed != 0,
+                    _ => {}
+                },
+                Ok(Message::Close(_)) | Err(Error::ConnectionClosed) => return Ok(()),
+                Ok(_) => {}
+                Err(Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        // The first frame is always sent
+        let frame = if fast_forward {
+            interpreter.run_frames(session.fast_forward_frames)?
+        } else {
+            interpreter.run_for(FRAME_DURATION)?
+        };
+        let screen_changed = frame.screen_changed || last_frame.is_empty();
+
+        // Only send frames that changed
+        if screen_changed {
+            let frame = pack_screen(&interpreter.screen);
+            if frame != last_frame {
+                match socket.send(Message::Binary(frame.clone())) {
+                    Err(Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => {}
+                    result => result?,
+                }
+                last_frame = frame;
+            }
+        }
+
+        // Events are queued for the current frame, so they're applied in arrival order
+        for applied in interpreter.take_applied_key_events() {
+            if let Some(received) = received.pop_front() {
+                latencies.push((received.elapsed(), applied.latency()));
+            }
+        }
+
+        next_frame += FRAME_DURATION;
+        thread::sleep(next_frame.saturating_duration_since(Instant::now()));
+    }
+}
+
+// Time from a key event reaching the server to the frame that applied it being sent,
+// along with the frames it waited for
+fn report_latency(latencies: &mut [(Duration, u64)]) {
+    if latencies.is_empty() {
+        return;
+    }
+    latencies.sort_unstable();
+    let percentile = |percent: usize| {
+        let (time, frames) = latencies[(latencies.len() - 1) * percent / 100];
+        format!("{:.1} ms ({frames} frames)", time.as_secs_f64() * 1000.)
+    };
+    println!(
+        "Key latency over {} events: min {}, median {}, 95th percentile {}, max {}",
+        latencies.len(),
+        percentile(0),
+        percentile(50),
+        percentile(95),
+        percentile(100)
+    );
+}
+
+// Width and height, then one bit per pixel, row by row, most significant bit first
+fn pack_screen(screen: &Screen) -> Vec<u8> {
+    let (width, height) = (screen.width(), screen.height());
+    let mut frame = vec![0; 2 + width * height / 8];
+    frame[0] = width as u8;
+    frame[1] = height as u8;
+
+    for y in 0..height {
+        for x in 0..width {
+            if screen.get(x, y) {
+                let pixel = y * width + x;
+                frame[2 + pixel / 8] |= 0b10000000 >> (pixel % 8);
+            }
+        }
+    }
+    frame
+}
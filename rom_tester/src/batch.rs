@@ -0,0 +1,162 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chippers_core::{
+    archive::read_roms,
+    interpreter::{Interpreter, Quirks},
+    scores::{HighScores, ScoreLocation},
+};
+use rayon::prelude::*;
+
+pub const INSTRUCTIONS_PER_FRAME: usize = 11;
+const HIGH_SCORES: usize = 10;
+
+pub struct BatchConfig {
+    // Number of 60 Hz frames each ROM runs for
+    pub frames: u64,
+    // Wall-clock limit for each ROM
+    pub timeout: Duration,
+    pub quirks: Quirks,
+    // Where each ROM keeps its score, and the directory its high-score table is saved in
+    pub score: Option<(ScoreLocation, PathBuf)>,
+}
+
+enum Outcome {
+    // Hash of the final screen, for comparing runs across versions
+    Completed {
+        screen_hash: u64,
+        // Final score and the path of the ROM's high-score table
+        score: Option<(u32, PathBuf)>,
+    },
+    Failed(String),
+    TimedOut {
+        frames: u64,
+    },
+}
+
+/// Runs every `.ch8` file in the directory, including those inside `.zip` and
+/// `.gz` archives, across all cores and prints a report.
+/// Returns whether every ROM completed without error.
+pub fn run(directory: &Path, config: &BatchConfig) -> io::Result<bool> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|extension| {
+                ["ch8", "zip", "gz"]
+                    .iter()
+                    .any(|known| extension.eq_ignore_ascii_case(known))
+            })
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut roms = Vec::new();
+    for path in paths {
+        match read_roms(&path) {
+            Ok(files) => roms.extend(files.into_iter().map(|rom| (rom.name, Ok(rom.data)))),
+            // An unreadable file or archive fails on its own, the other ROMs still run
+            Err(error) => roms.push((path.display().to_string(), Err(error))),
+        }
+    }
+
+    let results: Vec<(String, Outcome)> = roms
+        .into_par_iter()
+        .map(|(name, data)| {
+            let outcome = match data {
+                Ok(data) => run_rom(&data, config),
+                Err(error) => Outcome::Failed(error.to_string()),
+            };
+            (name, outcome)
+        })
+        .collect();
+
+    let (mut completed, mut failed, mut timed_out) = (0, 0, 0);
+    let mut unsaved_scores = 0;
+    for (name, outcome) in &results {
+        match outcome {
+            Outcome::Completed { screen_hash, score } => {
+                completed += 1;
+                print!("ok       {name} (screen {screen_hash:016x}");
+                if let Some((score, table)) = score {
+                    print!(", score {score}");
+                    // A score file that can't be written doesn't stop the report
+                    if let Err(error) = record_score(table, *score) {
+                        unsaved_scores += 1;
+                        print!(", score not saved: {error}");
+                    }
+                }
+                println!(")");
+            }
+            Outcome::Failed(error) => {
+                failed += 1;
+                println!("error    {name}: {error}");
+            }
+            Outcome::TimedOut { frames } => {
+                timed_out += 1;
+                println!("timeout  {name} (after {frames} frames)");
+            }
+        }
+    }
+
+    println!();
+    print!(
+        "{} ROMs: {completed} ok, {failed} failed, {timed_out} timed out",
+        results.len()
+    );
+    if unsaved_scores > 0 {
+        print!(", {unsaved_scores} scores not saved");
+    }
+    println!();
+
+    Ok(failed == 0 && timed_out == 0 && unsaved_scores == 0)
+}
+
+fn run_rom(program: &[u8], config: &BatchConfig) -> Outcome {
+    let mut interpreter = match Interpreter::new(program) {
+        Ok(interpreter) => interpreter,
+        Err(error) => return Outcome::Failed(error.to_string()),
+    };
+    interpreter.quirks = config.quirks;
+
+    let start = Instant::now();
+    for frame in 0..config.frames {
+        if start.elapsed() > config.timeout {
+            return Outcome::TimedOut { frames: frame };
+        }
+
+        if let Err(error) = interpreter.run_frame(INSTRUCTIONS_PER_FRAME) {
+            return Outcome::Failed(format!("{error} (frame {frame})"));
+        }
+        // The program exited on its own
+        if interpreter.is_halted() || interpreter.is_finished() {
+            break;
+        }
+    }
+
+    Outcome::Completed {
+        screen_hash: interpreter.screen.hash(),
+        score: config.score.as_ref().and_then(|(location, directory)| {
+            let score = location.read(&interpreter.ram)?;
+            Some((score, HighScores::path(directory, program)))
+        }),
+    }
+}
+
+// Adds a ROM's final score to its high-score table, reporting its rank if it placed
+fn record_score(path: &Path, score: u32) -> io::Result<()> {
+    let mut table = HighScores::load(path, HIGH_SCORES);
+    if let Some(rank) = table.submit(score) {
+        print!(", high score #{}", rank + 1);
+        table.save(path)?;
+    }
+    Ok(())
+}
@@ -1,14 +1,281 @@
-use chippers_core::interpreter::Interpreter;
+mod batch;
 
-fn main() {
+use std::{
+    io::BufRead,
+    path::Path,
+    process::ExitCode,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use batch::BatchConfig;
+use chippers_core::{
+    archive::read_roms,
+    decompiler::decompile,
+    disassembler::analyze,
+    instructions::decode,
+    interpreter::{Interpreter, KeyEvent, Quirks},
+    keymap::{Keymap, Layout},
+    patch::apply_patch,
+    replay::Movie,
+};
+
+const USAGE: &str =
+    "usage: chippers_rom_tester [--layout <qwerty|azerty|qwertz|dvorak|colemak> | --batch <directory> [--frames <n>] [--timeout <seconds>] [--quirks <cosmac|modern>] [--score <address:length:bcd|packed|binary> [--scores <directory>]] | --disassemble <rom> [--patch <ips|bps>] | --decompile <rom> [--patch <ips|bps>] | --migrate-state <state> <output> | --compare <rom> [--frames <n>] [--seed <n>]]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [] => run_display(Keymap::default()),
+        [flag, name] if flag == "--layout" => match name.parse::<Layout>() {
+            Ok(layout) => run_display(Keymap::preset(layout)),
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        },
+        [flag, directory, options @ ..] if flag == "--batch" => {
+            let Some(config) = parse_batch_options(Path::new(directory), options) else {
+                eprintln!("{USAGE}");
+                return ExitCode::FAILURE;
+            };
+            match batch::run(Path::new(directory), &config) {
+                Ok(true) => ExitCode::SUCCESS,
+                Ok(false) => ExitCode::FAILURE,
+                Err(error) => {
+                    eprintln!("{error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        [flag, rom] if flag == "--disassemble" || flag == "--decompile" => {
+            disassemble(rom, None, flag == "--decompile")
+        }
+        [flag, rom, patch_flag, patch]
+            if (flag == "--disassemble" || flag == "--decompile") && patch_flag == "--patch" =>
+        {
+            disassemble(rom, Some(patch), flag == "--decompile")
+        }
+        [flag, state, output] if flag == "--migrate-state" => migrate_state(state, output),
+        [flag, rom, options @ ..] if flag == "--compare" => {
+            let Some((frames, seed)) = parse_compare_options(options) else {
+                eprintln!("{USAGE}");
+                return ExitCode::FAILURE;
+            };
+            compare(rom, frames, seed)
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Upgrades a save state written by an older release to the current format
+fn migrate_state(state: &str, output: &str) -> ExitCode {
+    let migrated = std::fs::read(state)
+        .map_err(|error| error.to_string())
+        .and_then(|data| Interpreter::migrate_state(&data).map_err(|error| error.to_string()));
+    let migrated = match migrated {
+        Ok(migrated) => migrated,
+        Err(error) => {
+            eprintln!("{state}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(error) = std::fs::write(output, migrated) {
+        eprintln!("{output}: {error}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+// Archives are disassembled ROM by ROM. A patched ROM can be checked before it's run.
+// Decompiling prints Octo-like pseudo-code instead of the listing.
+fn disassemble(path: &str, patch_path: Option<&String>, decompiled: bool) -> ExitCode {
+    let roms = match read_roms(Path::new(path)) {
+        Ok(roms) => roms,
+        Err(error) => {
+            eprintln!("{path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut patch = None;
+    if let Some(patch_path) = patch_path {
+        match std::fs::read(patch_path) {
+            Ok(data) => patch = Some(data),
+            Err(error) => {
+                eprintln!("{patch_path}: {error}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    for rom in roms {
+        let program = match &patch {
+            Some(patch) => match apply_patch(&rom.data, patch) {
+                Ok(program) => program,
+                Err(error) => {
+                    eprintln!("{}: {error}", rom.name);
+                    return ExitCode::FAILURE;
+                }
+            },
+            None => rom.data,
+        };
+        let analysis = analyze(&program, 0x200);
+        if decompiled {
+            println!("# {}", rom.name);
+            print!("{}", decompile(&analysis));
+        } else {
+            println!("; {}", rom.name);
+            print!("{analysis}");
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+// Frame count and seed, 600 frames and a seed of 0 unless given
+fn parse_compare_options(options: &[String]) -> Option<(u64, u64)> {
+    let (mut frames, mut seed) = (600, 0);
+    for option in options.chunks(2) {
+        match option {
+            [name, value] if name == "--frames" => frames = value.parse().ok()?,
+            [name, value] if name == "--seed" => seed = value.parse().ok()?,
+            _ => return None,
+        }
+    }
+    Some((frames, seed))
+}
+
+// Runs the first ROM of the file with the COSMAC and the modern quirks side by side,
+// to find where a game depends on them
+fn compare(path: &str, frames: u64, seed: u64) -> ExitCode {
+    let program = match read_roms(Path::new(path)).map(|roms| roms.into_iter().next()) {
+        Ok(Some(rom)) => rom.data,
+        Ok(None) => {
+            eprintln!("no .ch8 file in {path}");
+            return ExitCode::FAILURE;
+        }
+        Err(error) => {
+            eprintln!("{path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let movie = Movie::new(seed, batch::INSTRUCTIONS_PER_FRAME as u32, Quirks::COSMAC);
+    match movie.compare(&program, Quirks::MODERN, frames) {
+        Ok(None) => {
+            println!("no difference in {frames} frames");
+            ExitCode::SUCCESS
+        }
+        Ok(Some(divergence)) => {
+            match divergence.instruction {
+                Some((address, opcode)) => match decode(opcode) {
+                    Ok(instruction) => println!(
+                        "runs differ in frame {} after {address:#05X}  {opcode:04X}  {instruction}",
+                        divergence.frame
+                    ),
+                    Err(_) => println!(
+                        "runs differ in frame {} after {address:#05X}  {opcode:04X}",
+                        divergence.frame
+                    ),
+                },
+                None => println!("runs differ at the end of frame {}", divergence.frame),
+            }
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("{path}: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// High-score tables are kept next to the ROMs unless `--scores` says otherwise
+fn parse_batch_options(directory: &Path, options: &[String]) -> Option<BatchConfig> {
+    let mut config = BatchConfig {
+        frames: 600,
+        timeout: Duration::from_secs(10),
+        quirks: Quirks::default(),
+        score: None,
+    };
+    let mut score_location = None;
+    let mut scores_directory = directory.to_path_buf();
+
+    for option in options.chunks(2) {
+        match option {
+            [name, value] if name == "--frames" => config.frames = value.parse().ok()?,
+            [name, value] if name == "--timeout" => {
+                config.timeout = Duration::from_secs_f64(value.parse().ok()?)
+            }
+            [name, value] if name == "--quirks" => {
+                config.quirks = match value.as_str() {
+                    "cosmac" => Quirks::COSMAC,
+                    "modern" => Quirks::MODERN,
+                    _ => return None,
+                }
+            }
+            [name, value] if name == "--score" => score_location = Some(value.parse().ok()?),
+            [name, value] if name == "--scores" => scores_directory = value.into(),
+            _ => return None,
+        }
+    }
+    config.score = score_location.map(|location| (location, scores_directory));
+
+    Some(config)
+}
+
+fn run_display(keymap: Keymap) -> ExitCode {
     let program = include_bytes!("../flags.ch8");
 
-    let mut interpreter = Interpreter::new(program);
+    let mut interpreter = Interpreter::new(program).expect("could not load program");
+    let typed = read_typed_characters();
 
     loop {
+        // Each typed key is held for one frame
+        for character in typed.try_iter() {
+            if let Some(key) = keymap.key(character) {
+                let frame = interpreter.frames();
+                for (pressed, frame) in [(true, frame), (false, frame + 1)] {
+                    interpreter.queue_key_event(KeyEvent {
+                        key,
+                        pressed,
+                        frame,
+                    });
+                }
+            }
+        }
+
         print!("{esc}c", esc = 27 as char);
-        interpreter.step();
+        if let Err(error) = interpreter.run_frame(12) {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
         println!("{}", interpreter.screen);
-        std::thread::sleep(std::time::Duration::from_nanos(1428571))
+        if interpreter.is_halted() || interpreter.is_finished() {
+            println!("Program ended");
+            return ExitCode::SUCCESS;
+        }
+        std::thread::sleep(Duration::from_secs(1) / 60)
     }
 }
+
+// The terminal is line buffered, so keys are typed followed by Enter
+fn read_typed_characters() -> Receiver<char> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            for character in line.chars() {
+                if sender.send(character).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    receiver
+}